@@ -36,7 +36,9 @@ pub mod dirs {
 
     pub static USER: Lazy<PathBuf> = Lazy::new(|| ROOT.join("user"));
     pub static COLLECTIONS: Lazy<PathBuf> = Lazy::new(|| ROOT.join("collections"));
+    pub static COLLECTION_BACKUPS: Lazy<PathBuf> = Lazy::new(|| ROOT.join("collection_backups"));
     pub static IMAGES: Lazy<PathBuf> = Lazy::new(|| ROOT.join("images"));
+    pub static BANLISTS: Lazy<PathBuf> = Lazy::new(|| ROOT.join("banlists"));
 
     macro_rules! create_lazy_dirs {
         ( $( $i:ident ),* ) => {
@@ -47,7 +49,14 @@ pub mod dirs {
     }
 
     pub fn init() -> Result<(), Box<dyn std::error::Error>> {
-        create_lazy_dirs!(ROOT, USER, COLLECTIONS, IMAGES);
+        create_lazy_dirs!(
+            ROOT,
+            USER,
+            COLLECTIONS,
+            COLLECTION_BACKUPS,
+            IMAGES,
+            BANLISTS
+        );
 
         Ok(())
     }
@@ -61,19 +70,51 @@ pub mod files {
     pub static DB: Lazy<PathBuf> = Lazy::new(|| dirs::ROOT.join("data.db"));
     pub static DB_BACKUP: Lazy<PathBuf> = Lazy::new(|| dirs::ROOT.join("data_bak.db"));
     pub static DB_VERSION: Lazy<PathBuf> = Lazy::new(|| dirs::ROOT.join("version.txt"));
+    pub static COLLECTION_INDEX: Lazy<PathBuf> = Lazy::new(|| dirs::ROOT.join("collection_index"));
 }
 
 pub mod images {
     use gtk::Image;
     use relm4::gtk;
 
-    use super::dirs;
+    use crate::images::{cached_path, ImageKind};
 
+    pub const PLACEHOLDER_ICON: &str = "image-missing-symbolic";
+
+    /// Loads a cached card image, falling back to [`placeholder`] if it has not been
+    /// downloaded.
     pub fn load_card(id: u32) -> Image {
-        let filename = dirs::IMAGES.join(format!("{}.jpg", id));
+        let filename = cached_path(id, ImageKind::Full);
 
-        Image::from_file(filename)
+        if filename.is_file() {
+            Image::from_file(filename)
+        } else {
+            placeholder()
+        }
     }
+
+    pub fn placeholder() -> Image {
+        Image::from_icon_name(PLACEHOLDER_ICON)
+    }
+}
+
+/// Points [`dirs`] at an empty directory for the whole test run. The returned guard
+/// serializes tests that touch files in it.
+#[cfg(test)]
+pub fn test_dirs() -> std::sync::MutexGuard<'static, ()> {
+    use std::sync::{Mutex, Once};
+
+    static INIT: Once = Once::new();
+    static LOCK: Mutex<()> = Mutex::new(());
+
+    INIT.call_once(|| {
+        let root = std::env::temp_dir().join(format!("{}_tests", APP_ID_PARTS.2));
+        let _ = std::fs::remove_dir_all(&root);
+        std::env::set_var("XDG_DATA_HOME", &root);
+        dirs::init().expect("Failed to create the test directories.");
+    });
+
+    LOCK.lock().unwrap_or_else(|error| error.into_inner())
 }
 
 pub fn get_or_log<T, E: Debug>(value: Result<T, E>, fallback: T) -> T {
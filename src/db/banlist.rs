@@ -0,0 +1,421 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::user_data::collection::CardType;
+
+/// Game format a banlist applies to.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Tcg,
+    Ocg,
+    Goat,
+}
+
+impl Format {
+    pub const ALL: [Format; 3] = [Format::Tcg, Format::Ocg, Format::Goat];
+
+    /// Name of the format as stored in the database.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Format::Tcg => "tcg",
+            Format::Ocg => "ocg",
+            Format::Goat => "goat",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum LimitStatus {
+    Forbidden,
+    Limited,
+    #[serde(rename = "Semi-Limited")]
+    SemiLimited,
+    #[default]
+    Unlimited,
+}
+
+impl LimitStatus {
+    pub fn max_copies(&self) -> u8 {
+        match self {
+            LimitStatus::Forbidden => 0,
+            LimitStatus::Limited => 1,
+            LimitStatus::SemiLimited => 2,
+            LimitStatus::Unlimited => 3,
+        }
+    }
+
+    /// Name of the status as stored in the database.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LimitStatus::Forbidden => "Forbidden",
+            LimitStatus::Limited => "Limited",
+            LimitStatus::SemiLimited => "Semi-Limited",
+            LimitStatus::Unlimited => "Unlimited",
+        }
+    }
+}
+
+impl FromStr for LimitStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Forbidden" | "Banned" => Ok(LimitStatus::Forbidden),
+            "Limited" => Ok(LimitStatus::Limited),
+            "Semi-Limited" => Ok(LimitStatus::SemiLimited),
+            "Unlimited" => Ok(LimitStatus::Unlimited),
+            _ => Err(format!("Unknown limit status `{s}`.")),
+        }
+    }
+}
+
+/// Unknown statuses are logged and treated as unlimited.
+fn parse_status(status: String) -> LimitStatus {
+    status.parse().unwrap_or_else(|error| {
+        log::warn!("{error}");
+        LimitStatus::Unlimited
+    })
+}
+
+/// Current limit status of the card with `id` in `format`.
+pub fn get_status(
+    connection: &Connection,
+    id: CardType,
+    format: Format,
+) -> rusqlite::Result<LimitStatus> {
+    Ok(connection
+        .query_row(
+            "SELECT status FROM banlists WHERE card_id = ?1 AND format = ?2",
+            (id, format.as_str()),
+            |row| row.get(0),
+        )
+        .optional()?
+        .map_or(LimitStatus::Unlimited, parse_status))
+}
+
+/// Current limit status of the card with `id` in every format.
+pub fn get_statuses(
+    connection: &Connection,
+    id: CardType,
+) -> rusqlite::Result<HashMap<Format, LimitStatus>> {
+    Format::ALL
+        .iter()
+        .map(|format| Ok((*format, get_status(connection, id, *format)?)))
+        .collect()
+}
+
+/// All cards that are not unlimited in `format`.
+pub fn get_banlist(
+    connection: &Connection,
+    format: Format,
+) -> rusqlite::Result<HashMap<CardType, LimitStatus>> {
+    connection
+        .prepare_cached("SELECT card_id, status FROM banlists WHERE format = ?1")?
+        .query_map([format.as_str()], |row| {
+            Ok((row.get(0)?, parse_status(row.get(1)?)))
+        })?
+        .collect()
+}
+
+/// Banlist of a single format that took effect on `date`, as read from a JSON file:
+///
+/// ```json
+/// {
+///     "date": "2005-04-01",
+///     "format": "tcg",
+///     "cards": {
+///         "Forbidden": [55144522],
+///         "Limited": [],
+///         "Semi-Limited": []
+///     }
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BanlistSnapshot {
+    /// Date in `YYYY-MM-DD` format, comparable with `sets.date`.
+    pub date: String,
+    pub format: Format,
+    pub cards: HashMap<LimitStatus, Vec<CardType>>,
+}
+
+/// Stores `snapshot`, replacing any snapshot with the same date and format.
+pub fn import_snapshot(
+    connection: &mut Connection,
+    snapshot: &BanlistSnapshot,
+) -> rusqlite::Result<()> {
+    let transaction = connection.transaction()?;
+
+    transaction.execute(
+        "DELETE FROM banlist_snapshots WHERE date = ?1 AND format = ?2",
+        (&snapshot.date, snapshot.format.as_str()),
+    )?;
+
+    {
+        let mut statement = transaction.prepare(
+            "INSERT INTO banlist_snapshots (date, format, card_id, status)
+            VALUES (?1, ?2, ?3, ?4)",
+        )?;
+
+        for (status, ids) in snapshot.cards.iter() {
+            for id in ids {
+                statement.execute((
+                    &snapshot.date,
+                    snapshot.format.as_str(),
+                    id,
+                    status.as_str(),
+                ))?;
+            }
+        }
+    }
+
+    transaction.commit()
+}
+
+pub fn import_snapshot_file(
+    connection: &mut Connection,
+    path: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let snapshot: BanlistSnapshot = serde_json::from_str(&fs::read_to_string(path)?)?;
+    import_snapshot(connection, &snapshot)?;
+
+    Ok(())
+}
+
+/// Imports every `.json` file in `dir` and returns the number of imported snapshots.
+/// Files that fail to import are logged and skipped.
+pub fn import_snapshots_from_dir(
+    connection: &mut Connection,
+    dir: &Path,
+) -> Result<usize, Box<dyn Error>> {
+    let mut imported = 0;
+
+    for entry in dir.read_dir()? {
+        let path = entry?.path();
+
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            match import_snapshot_file(connection, &path) {
+                Ok(()) => imported += 1,
+                Err(error) => log::error!("Failed to import {}: {:?}", path.display(), error),
+            }
+        }
+    }
+
+    Ok(imported)
+}
+
+/// Limits that applied in `format` on `date` (`YYYY-MM-DD`), taken from the most recent
+/// snapshot on or before that date. Returns `None` if there is no such snapshot.
+pub fn limits_on(
+    connection: &Connection,
+    date: &str,
+    format: Format,
+) -> rusqlite::Result<Option<HashMap<CardType, LimitStatus>>> {
+    let snapshot_date: Option<String> = connection.query_row(
+        "SELECT MAX(date) FROM banlist_snapshots WHERE date <= ?1 AND format = ?2",
+        (date, format.as_str()),
+        |row| row.get(0),
+    )?;
+
+    let Some(snapshot_date) = snapshot_date else {
+        return Ok(None);
+    };
+
+    connection
+        .prepare_cached(
+            "SELECT card_id, status FROM banlist_snapshots WHERE date = ?1 AND format = ?2",
+        )?
+        .query_map((snapshot_date, format.as_str()), |row| {
+            Ok((row.get(0)?, parse_status(row.get(1)?)))
+        })?
+        .collect::<rusqlite::Result<_>>()
+        .map(Some)
+}
+
+/// Limits that applied in `format` when the set called `set_name` was released.
+/// Returns `None` if the set has no release date or no snapshot covers it.
+pub fn limits_for_set(
+    connection: &Connection,
+    set_name: &str,
+    format: Format,
+) -> rusqlite::Result<Option<HashMap<CardType, LimitStatus>>> {
+    let date: Option<String> = connection
+        .query_row("SELECT date FROM sets WHERE name = ?1", [set_name], |row| {
+            row.get(0)
+        })
+        .optional()?
+        .flatten();
+
+    match date {
+        Some(date) => limits_on(connection, &date, format),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::testing::fixture_database;
+
+    #[test]
+    fn update_stores_limits() {
+        let connection = fixture_database();
+
+        let rows: Vec<(CardType, String, String)> = connection
+            .prepare("SELECT card_id, format, status FROM banlists ORDER BY card_id, format")
+            .unwrap()
+            .query_map((), |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            [
+                (55144522, "goat".to_string(), "Limited".to_string()),
+                (55144522, "ocg".to_string(), "Forbidden".to_string()),
+                (55144522, "tcg".to_string(), "Forbidden".to_string()),
+                (89631139, "goat".to_string(), "Limited".to_string()),
+            ]
+        );
+
+        assert_eq!(
+            get_status(&connection, 89631139, Format::Goat).unwrap(),
+            LimitStatus::Limited
+        );
+        assert_eq!(
+            get_status(&connection, 89631139, Format::Tcg).unwrap(),
+            LimitStatus::Unlimited
+        );
+        assert_eq!(
+            get_statuses(&connection, 55144522).unwrap(),
+            HashMap::from([
+                (Format::Tcg, LimitStatus::Forbidden),
+                (Format::Ocg, LimitStatus::Forbidden),
+                (Format::Goat, LimitStatus::Limited),
+            ])
+        );
+        assert_eq!(
+            get_banlist(&connection, Format::Goat).unwrap(),
+            HashMap::from([
+                (55144522, LimitStatus::Limited),
+                (89631139, LimitStatus::Limited),
+            ])
+        );
+        assert!(get_banlist(&connection, Format::Tcg)
+            .unwrap()
+            .keys()
+            .eq([&55144522]));
+    }
+
+    fn snapshot(date: &str, format: Format, cards: &[(LimitStatus, CardType)]) -> BanlistSnapshot {
+        let mut snapshot = BanlistSnapshot {
+            date: date.to_string(),
+            format,
+            cards: HashMap::new(),
+        };
+        for (status, id) in cards {
+            snapshot.cards.entry(*status).or_default().push(*id);
+        }
+        snapshot
+    }
+
+    #[test]
+    fn limits_between_snapshots() {
+        let mut connection = fixture_database();
+        let pot = (LimitStatus::Forbidden, 55144522);
+        let eyes = (LimitStatus::Limited, 89631139);
+
+        import_snapshot(
+            &mut connection,
+            &snapshot("2002-03-01", Format::Tcg, &[eyes]),
+        )
+        .unwrap();
+        import_snapshot(
+            &mut connection,
+            &snapshot("2002-03-01", Format::Tcg, &[pot]),
+        )
+        .unwrap();
+        import_snapshot(
+            &mut connection,
+            &snapshot("2002-06-01", Format::Tcg, &[pot, eyes]),
+        )
+        .unwrap();
+        import_snapshot(
+            &mut connection,
+            &snapshot("2002-04-01", Format::Ocg, &[eyes]),
+        )
+        .unwrap();
+
+        let limits = |date| limits_on(&connection, date, Format::Tcg).unwrap();
+        let first = Some(HashMap::from([(55144522, LimitStatus::Forbidden)]));
+        let second = Some(HashMap::from([
+            (55144522, LimitStatus::Forbidden),
+            (89631139, LimitStatus::Limited),
+        ]));
+
+        assert_eq!(limits("2002-02-28"), None);
+        assert_eq!(limits("2002-03-01"), first);
+        assert_eq!(limits("2002-04-15"), first);
+        assert_eq!(limits("2002-06-01"), second);
+        assert_eq!(limits("2010-01-01"), second);
+        assert_eq!(
+            limits_on(&connection, "2002-06-01", Format::Ocg).unwrap(),
+            Some(HashMap::from([(89631139, LimitStatus::Limited)]))
+        );
+        assert_eq!(
+            limits_on(&connection, "2002-06-01", Format::Goat).unwrap(),
+            None
+        );
+
+        // LOB was released on 2002-03-08 and MRD on 2002-06-26.
+        assert_eq!(
+            limits_for_set(&connection, "Legend of Blue Eyes White Dragon", Format::Tcg).unwrap(),
+            first
+        );
+        assert_eq!(
+            limits_for_set(&connection, "Metal Raiders", Format::Tcg).unwrap(),
+            second
+        );
+        assert_eq!(
+            limits_for_set(&connection, "Unknown Set", Format::Tcg).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn duplicate_cards_roll_back() {
+        let mut connection = fixture_database();
+        let pot = (LimitStatus::Forbidden, 55144522);
+
+        import_snapshot(
+            &mut connection,
+            &snapshot("2002-03-01", Format::Tcg, &[pot]),
+        )
+        .unwrap();
+        let duplicate = snapshot(
+            "2002-03-01",
+            Format::Tcg,
+            &[
+                (LimitStatus::Limited, 89631139),
+                pot,
+                (LimitStatus::Limited, 55144522),
+            ],
+        );
+        assert!(import_snapshot(&mut connection, &duplicate).is_err());
+
+        assert_eq!(
+            limits_on(&connection, "2002-03-01", Format::Tcg).unwrap(),
+            Some(HashMap::from([(55144522, LimitStatus::Forbidden)]))
+        );
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+
+use rusqlite::{Connection, OptionalExtension, Row};
+
+use crate::user_data::collection::{Card, CardType};
+
+/// A single row of the `cards` table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CardInfo {
+    pub id: CardType,
+    pub name: String,
+    pub card_type: String,
+    pub description: String,
+    pub atk: Option<i32>,
+    pub def: Option<i32>,
+    pub level: Option<u8>,
+    pub race: Option<String>,
+    pub attribute: Option<String>,
+    pub archetype: Option<String>,
+    pub pend_scale: Option<u8>,
+    pub link_rating: Option<u8>,
+}
+
+impl CardInfo {
+    /// Columns in the order expected by [`CardInfo::from_row`].
+    pub const COLUMNS: &'static str = "cards.id, cards.name, cards.card_type, cards.description, \
+        cards.atk, cards.def, cards.level, cards.type, cards.attribute, cards.archetype, \
+        cards.pend_scale, cards.link_rating";
+
+    pub fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            card_type: row.get(2)?,
+            description: row.get(3)?,
+            atk: row.get(4)?,
+            def: row.get(5)?,
+            level: row.get(6)?,
+            race: row.get(7)?,
+            attribute: row.get(8)?,
+            archetype: row.get(9)?,
+            pend_scale: row.get(10)?,
+            link_rating: row.get(11)?,
+        })
+    }
+
+    /// Whether the card belongs in the extra deck, i.e. is a Fusion, Synchro, Xyz or
+    /// Link monster.
+    pub fn is_extra_deck(&self) -> bool {
+        let card_type = self.card_type.to_lowercase();
+
+        ["fusion", "synchro", "xyz", "link"]
+            .iter()
+            .any(|extra_type| card_type.contains(extra_type))
+    }
+}
+
+fn query_all<P: rusqlite::Params>(
+    connection: &Connection,
+    sql: &str,
+    params: P,
+) -> rusqlite::Result<Vec<CardInfo>> {
+    connection
+        .prepare_cached(sql)?
+        .query_map(params, CardInfo::from_row)?
+        .collect()
+}
+
+pub fn get(connection: &Connection, id: CardType) -> rusqlite::Result<Option<CardInfo>> {
+    connection
+        .query_row(
+            &format!("SELECT {} FROM cards WHERE id = ?1", CardInfo::COLUMNS),
+            [id],
+            CardInfo::from_row,
+        )
+        .optional()
+}
+
+/// Looks up a card by its exact name, ignoring case.
+pub fn get_by_name(connection: &Connection, name: &str) -> rusqlite::Result<Option<CardInfo>> {
+    connection
+        .query_row(
+            &format!(
+                "SELECT {} FROM cards WHERE name = ?1 COLLATE NOCASE",
+                CardInfo::COLUMNS
+            ),
+            [name],
+            CardInfo::from_row,
+        )
+        .optional()
+}
+
+pub fn get_by_archetype(
+    connection: &Connection,
+    archetype: &str,
+) -> rusqlite::Result<Vec<CardInfo>> {
+    query_all(
+        connection,
+        &format!(
+            "SELECT {} FROM cards WHERE archetype = ?1 ORDER BY name",
+            CardInfo::COLUMNS
+        ),
+        [archetype],
+    )
+}
+
+/// Returns every card printed in the set called `set_name`.
+pub fn get_by_set(connection: &Connection, set_name: &str) -> rusqlite::Result<Vec<CardInfo>> {
+    query_all(
+        connection,
+        &format!(
+            "SELECT DISTINCT {} FROM cards
+            JOIN set_contents ON set_contents.card_id = cards.id
+            WHERE set_contents.set_name = ?1
+            ORDER BY cards.name",
+            CardInfo::COLUMNS
+        ),
+        [set_name],
+    )
+}
+
+/// Looks up all `ids` in a single query. Ids that are not in the database are missing
+/// from the result.
+pub fn get_many(
+    connection: &Connection,
+    ids: &[CardType],
+) -> rusqlite::Result<HashMap<CardType, CardInfo>> {
+    let ids_json = serde_json::to_string(ids).expect("A list of integers is valid JSON");
+
+    Ok(query_all(
+        connection,
+        &format!(
+            "SELECT {} FROM cards WHERE id IN (SELECT value FROM json_each(?1))",
+            CardInfo::COLUMNS
+        ),
+        [ids_json],
+    )?
+    .into_iter()
+    .map(|info| (info.id, info))
+    .collect())
+}
+
+/// Resolves the cards of a collection, sorted by name. Cards that are not in the
+/// database are skipped.
+pub fn get_for_collection(
+    connection: &Connection,
+    cards: &HashMap<Card, u8>,
+) -> rusqlite::Result<Vec<(CardInfo, u8)>> {
+    let ids: Vec<CardType> = cards.keys().map(|card| card.id).collect();
+    let mut infos = get_many(connection, &ids)?;
+
+    let mut result: Vec<(CardInfo, u8)> = cards
+        .iter()
+        .filter_map(|(card, quantity)| infos.remove(&card.id).map(|info| (info, *quantity)))
+        .collect();
+    result.sort_by(|(first, _), (second, _)| first.name.cmp(&second.name));
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::testing::fixture_database;
+
+    const BLUE_EYES: CardType = 89631139;
+    const POT_OF_GREED: CardType = 55144522;
+    const THOUSAND_DRAGON: CardType = 25451652;
+    const DECODE_TALKER: CardType = 1861629;
+
+    fn names(infos: &[CardInfo]) -> Vec<&str> {
+        infos.iter().map(|info| info.name.as_str()).collect()
+    }
+
+    #[test]
+    fn get_maps_columns() {
+        let connection = fixture_database();
+
+        let blue_eyes = get(&connection, BLUE_EYES).unwrap().unwrap();
+        assert_eq!(blue_eyes.name, "Blue-Eyes White Dragon");
+        assert_eq!(blue_eyes.card_type, "Normal Monster");
+        assert_eq!((blue_eyes.atk, blue_eyes.def), (Some(3000), Some(2500)));
+        assert_eq!(blue_eyes.level, Some(8));
+        assert_eq!(blue_eyes.race.as_deref(), Some("Dragon"));
+        assert_eq!(blue_eyes.attribute.as_deref(), Some("LIGHT"));
+        assert_eq!(blue_eyes.archetype.as_deref(), Some("Blue-Eyes"));
+        assert!(!blue_eyes.is_extra_deck());
+
+        let pot_of_greed = get(&connection, POT_OF_GREED).unwrap().unwrap();
+        assert_eq!((pot_of_greed.atk, pot_of_greed.level), (None, None));
+
+        let decode_talker = get(&connection, DECODE_TALKER).unwrap().unwrap();
+        assert_eq!(decode_talker.link_rating, Some(3));
+        assert!(decode_talker.is_extra_deck());
+        assert!(get(&connection, THOUSAND_DRAGON)
+            .unwrap()
+            .unwrap()
+            .is_extra_deck());
+
+        assert_eq!(get(&connection, 1).unwrap(), None);
+    }
+
+    #[test]
+    fn lookups() {
+        let connection = fixture_database();
+
+        assert_eq!(
+            get_by_name(&connection, "blue-eyes WHITE dragon")
+                .unwrap()
+                .map(|info| info.id),
+            Some(BLUE_EYES)
+        );
+        assert_eq!(get_by_name(&connection, "Blue-Eyes").unwrap(), None);
+        assert_eq!(
+            names(&get_by_archetype(&connection, "Blue-Eyes").unwrap()),
+            ["Blue-Eyes White Dragon"]
+        );
+        assert_eq!(
+            names(&get_by_set(&connection, "Starter Deck: Yugi").unwrap()),
+            ["Dark Magician", "Kuriboh"]
+        );
+        assert!(get_by_set(&connection, "Unknown").unwrap().is_empty());
+    }
+
+    #[test]
+    fn many() {
+        let connection = fixture_database();
+
+        let infos = get_many(&connection, &[BLUE_EYES, POT_OF_GREED, 1]).unwrap();
+        let mut ids: Vec<CardType> = infos.keys().copied().collect();
+        ids.sort_unstable();
+        assert_eq!(ids, [POT_OF_GREED, BLUE_EYES]);
+
+        let cards = HashMap::from([
+            (Card { id: POT_OF_GREED }, 1),
+            (Card { id: BLUE_EYES }, 3),
+            (Card { id: 1 }, 2),
+        ]);
+        let resolved: Vec<(String, u8)> = get_for_collection(&connection, &cards)
+            .unwrap()
+            .into_iter()
+            .map(|(info, quantity)| (info.name, quantity))
+            .collect();
+        assert_eq!(
+            resolved,
+            [
+                ("Blue-Eyes White Dragon".to_string(), 3),
+                ("Pot of Greed".to_string(), 1)
+            ]
+        );
+    }
+}
@@ -0,0 +1,335 @@
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+use rusqlite::Connection;
+
+/// Ordered schema migrations. The schema version of a database (stored in
+/// `PRAGMA user_version`) is the number of migrations that have been applied to it.
+///
+/// Migrations must never be edited or reordered once released, only appended.
+const MIGRATIONS: &[&str] = &[
+    // 1: Initial schema. Uses `IF NOT EXISTS` to adopt databases created before
+    // schema versioning was introduced.
+    "CREATE TABLE IF NOT EXISTS sets (
+        name    TEXT PRIMARY KEY,
+        date    TEXT,
+        code    TEXT,
+        cards   INTEGER
+    );
+    CREATE TABLE IF NOT EXISTS cards (
+        id          INTEGER PRIMARY KEY,
+        name        TEXT,
+        card_type   TEXT,
+        description TEXT,
+        atk         INTEGER,
+        def         INTEGER,
+        level       INTEGER,
+        type        TEXT,
+        attribute   TEXT,
+        archetype   TEXT,
+        pend_scale  INTEGER,
+        link_rating INTEGER
+    );
+    CREATE TABLE IF NOT EXISTS set_contents (
+        card_id     INTEGER REFERENCES cards(id),
+        set_name    TEXT,
+        rarity      TEXT
+    );",
+    // 2: Full-text search index over card names and descriptions.
+    "CREATE VIRTUAL TABLE cards_fts USING fts5(
+        name,
+        description,
+        content='cards',
+        content_rowid='id'
+    );
+    INSERT INTO cards_fts(cards_fts) VALUES('rebuild');",
+    // 3: Current Forbidden & Limited status of cards per format.
+    "CREATE TABLE banlists (
+        card_id     INTEGER REFERENCES cards(id),
+        format      TEXT,
+        status      TEXT,
+        PRIMARY KEY (card_id, format)
+    );",
+    // 4: Dated banlist snapshots. These are imported separately from the card data,
+    // so they must survive `db::update` and do not reference `cards`.
+    "CREATE TABLE banlist_snapshots (
+        date        TEXT,
+        format      TEXT,
+        card_id     INTEGER,
+        status      TEXT,
+        PRIMARY KEY (date, format, card_id)
+    );",
+    // 5: Printing details in `set_contents` and a reference to `sets`. Rows of sets
+    // that are not in `sets` cannot be kept.
+    "CREATE TABLE set_contents_new (
+        card_id     INTEGER REFERENCES cards(id),
+        set_name    TEXT REFERENCES sets(name),
+        rarity      TEXT,
+        set_code    TEXT,
+        rarity_code TEXT,
+        price       REAL
+    );
+    INSERT INTO set_contents_new (card_id, set_name, rarity)
+        SELECT card_id, set_name, rarity FROM set_contents
+        WHERE set_name IN (SELECT name FROM sets);
+    DROP TABLE set_contents;
+    ALTER TABLE set_contents_new RENAME TO set_contents;",
+];
+
+/// Schema version this build of the app expects.
+pub const SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+#[derive(Debug)]
+pub enum MigrationError {
+    /// The database was created by a newer version of the app.
+    TooNew {
+        found: u32,
+        supported: u32,
+    },
+    Sqlite(rusqlite::Error),
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooNew { found, supported } => write!(
+                f,
+                "Database schema version {found} is newer than the supported version {supported}."
+            ),
+            Self::Sqlite(error) => write!(f, "Failed to migrate database: {error}"),
+        }
+    }
+}
+
+impl Error for MigrationError {}
+
+impl From<rusqlite::Error> for MigrationError {
+    fn from(error: rusqlite::Error) -> Self {
+        Self::Sqlite(error)
+    }
+}
+
+pub fn schema_version(connection: &Connection) -> rusqlite::Result<u32> {
+    connection.pragma_query_value(None, "user_version", |row| row.get(0))
+}
+
+/// Applies all pending migrations inside a single transaction.
+///
+/// Databases with a schema version newer than [`SCHEMA_VERSION`] are left untouched.
+pub fn migrate(connection: &mut Connection) -> Result<(), MigrationError> {
+    let version = schema_version(connection)?;
+
+    if version > SCHEMA_VERSION {
+        return Err(MigrationError::TooNew {
+            found: version,
+            supported: SCHEMA_VERSION,
+        });
+    }
+
+    let transaction = connection.transaction()?;
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        log::info!("Migrating database to schema version {}.", index + 1);
+        transaction.execute_batch(migration)?;
+        transaction.pragma_update(None, "user_version", index as u32 + 1)?;
+    }
+
+    transaction.commit()?;
+
+    Ok(())
+}
+
+/// Opens the database at `path` and migrates it to [`SCHEMA_VERSION`].
+pub fn open(path: &Path) -> Result<Connection, MigrationError> {
+    let mut connection = Connection::open(path)?;
+    migrate(&mut connection)?;
+
+    Ok(connection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// In-memory database with only the first `version` migrations applied.
+    fn database_at(version: u32) -> Connection {
+        let connection = Connection::open_in_memory().unwrap();
+        for migration in &MIGRATIONS[..version as usize] {
+            connection.execute_batch(migration).unwrap();
+        }
+        connection
+            .pragma_update(None, "user_version", version)
+            .unwrap();
+
+        connection
+    }
+
+    fn schema(connection: &Connection) -> Vec<(String, String, String)> {
+        connection
+            .prepare("SELECT type, name, COALESCE(sql, '') FROM sqlite_master ORDER BY name")
+            .unwrap()
+            .query_map((), |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap()
+    }
+
+    fn columns(connection: &Connection, table: &str) -> Vec<String> {
+        connection
+            .prepare(&format!("PRAGMA table_info({table})"))
+            .unwrap()
+            .query_map((), |row| row.get(1))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn migrates_from_every_version() {
+        let expected = schema(&database_at(SCHEMA_VERSION));
+
+        for version in 0..SCHEMA_VERSION {
+            let mut connection = database_at(version);
+            migrate(&mut connection).unwrap();
+
+            assert_eq!(schema_version(&connection).unwrap(), SCHEMA_VERSION);
+            assert_eq!(schema(&connection), expected, "from version {version}");
+        }
+    }
+
+    #[test]
+    fn final_schema() {
+        let mut connection = database_at(0);
+        migrate(&mut connection).unwrap();
+
+        let tables: Vec<String> = schema(&connection)
+            .into_iter()
+            .filter(|(kind, _, _)| kind == "table")
+            .map(|(_, name, _)| name)
+            .collect();
+        for table in [
+            "sets",
+            "cards",
+            "set_contents",
+            "cards_fts",
+            "banlists",
+            "banlist_snapshots",
+        ] {
+            assert!(tables.iter().any(|name| name == table), "missing {table}");
+        }
+        assert!(!tables.iter().any(|name| name == "set_contents_new"));
+
+        assert_eq!(
+            columns(&connection, "set_contents"),
+            [
+                "card_id",
+                "set_name",
+                "rarity",
+                "set_code",
+                "rarity_code",
+                "price"
+            ]
+        );
+        assert_eq!(
+            columns(&connection, "banlist_snapshots"),
+            ["date", "format", "card_id", "status"]
+        );
+    }
+
+    #[test]
+    fn printings_of_unknown_sets_are_dropped() {
+        let mut connection = database_at(4);
+        connection
+            .execute_batch(
+                "INSERT INTO sets (name) VALUES ('Known');
+                INSERT INTO cards (id) VALUES (1), (2);
+                INSERT INTO set_contents VALUES (1, 'Known', 'Common'), (2, 'Unknown', 'Rare');",
+            )
+            .unwrap();
+
+        migrate(&mut connection).unwrap();
+
+        let rows: Vec<(u32, String, String)> = connection
+            .prepare("SELECT card_id, set_name, rarity FROM set_contents")
+            .unwrap()
+            .query_map((), |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(rows, [(1, "Known".to_string(), "Common".to_string())]);
+    }
+
+    #[test]
+    fn adopts_unversioned_database() {
+        // The tables `db::update` created before schema versioning was introduced.
+        let mut connection = Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE sets (
+                    name    TEXT PRIMARY KEY,
+                    date    TEXT,
+                    code    TEXT,
+                    cards   INTEGER
+                );
+                CREATE TABLE cards (
+                    id          INTEGER PRIMARY KEY,
+                    name        TEXT,
+                    card_type   TEXT,
+                    description TEXT,
+                    atk         INTEGER,
+                    def         INTEGER,
+                    level       INTEGER,
+                    type        TEXT,
+                    attribute   TEXT,
+                    archetype   TEXT,
+                    pend_scale  INTEGER,
+                    link_rating INTEGER
+                );
+                CREATE TABLE set_contents (
+                    card_id     INTEGER REFERENCES cards(id),
+                    set_name    TEXT,
+                    rarity      TEXT
+                );
+                INSERT INTO sets (name) VALUES ('Legend of Blue Eyes White Dragon');
+                INSERT INTO cards (id, name, description)
+                    VALUES (89631139, 'Blue-Eyes White Dragon', 'This legendary dragon');
+                INSERT INTO set_contents
+                    VALUES (89631139, 'Legend of Blue Eyes White Dragon', 'Ultra Rare');",
+            )
+            .unwrap();
+
+        migrate(&mut connection).unwrap();
+
+        assert_eq!(schema_version(&connection).unwrap(), SCHEMA_VERSION);
+        let count: u32 = connection
+            .query_row("SELECT COUNT(*) FROM set_contents", (), |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+        let id: u32 = connection
+            .query_row(
+                "SELECT rowid FROM cards_fts WHERE cards_fts MATCH 'legendary'",
+                (),
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(id, 89631139);
+    }
+
+    #[test]
+    fn rejects_newer_database() {
+        let mut connection = Connection::open_in_memory().unwrap();
+        connection
+            .pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .unwrap();
+
+        match migrate(&mut connection) {
+            Err(MigrationError::TooNew { found, supported }) => {
+                assert_eq!(found, SCHEMA_VERSION + 1);
+                assert_eq!(supported, SCHEMA_VERSION);
+            }
+            result => panic!("expected `TooNew`, got {result:?}"),
+        }
+        assert_eq!(schema_version(&connection).unwrap(), SCHEMA_VERSION + 1);
+    }
+}
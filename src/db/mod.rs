@@ -0,0 +1,401 @@
+use std::error::Error;
+use std::fs;
+use std::sync::atomic::AtomicBool;
+
+use once_cell::sync::OnceCell;
+use rusqlite::Connection;
+use serde::Deserialize;
+
+use crate::data::{files, get_or_log};
+
+pub mod banlist;
+pub mod cards;
+pub mod migrations;
+mod progress;
+pub use progress::*;
+pub mod search;
+pub mod sets;
+pub mod source;
+pub use source::CardDataSource;
+
+#[derive(Deserialize)]
+struct DBVersion {
+    database_version: String,
+}
+
+impl DBVersion {
+    fn get_version(self) -> String {
+        self.database_version
+    }
+}
+
+pub fn new_version_available(source: &dyn CardDataSource) -> Result<bool, Box<dyn Error>> {
+    if let Some(local_version) = get_local_version()? {
+        Ok(local_version != get_upstream_version(source)?)
+    } else {
+        Ok(true)
+    }
+}
+
+pub fn update_local_version(source: &dyn CardDataSource) -> Result<(), Box<dyn Error>> {
+    fs::write(files::DB_VERSION.as_path(), get_upstream_version(source)?)?;
+
+    Ok(())
+}
+
+pub fn get_upstream_version(source: &dyn CardDataSource) -> Result<String, Box<dyn Error>> {
+    Ok(
+        serde_json::from_str::<Vec<DBVersion>>(&source.fetch_version()?)?
+            .pop()
+            .ok_or("The database version response is empty.")?
+            .get_version(),
+    )
+}
+
+pub fn get_local_version() -> Result<Option<String>, Box<dyn Error>> {
+    if files::DB_VERSION.is_file() {
+        Ok(Some(fs::read_to_string(files::DB_VERSION.as_path())?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Updates the database, restoring the backup if the update was cancelled or left the
+/// database unusable. Returns whether the update was successful.
+pub fn update_or_restore(
+    connection: &mut OnceCell<Connection>,
+    source: &dyn CardDataSource,
+    progress: &mut dyn FnMut(UpdateStage),
+    cancel: &AtomicBool,
+) -> Result<bool, Box<dyn Error>> {
+    create_backup()?;
+    let res = update_with_progress(connection.get_mut().unwrap(), source, progress, cancel);
+
+    if res.is_ok() {
+        Ok(true)
+    } else {
+        let cancelled = res.as_ref().is_err_and(|error| error.is::<Cancelled>());
+        get_or_log(res, ());
+
+        // A failed update is rolled back, so the backup is only needed if the
+        // database itself is no longer usable.
+        if !cancelled && tables_intact(connection.get().unwrap()) {
+            return Ok(false);
+        }
+
+        connection.take();
+        restore_backup()?;
+
+        connection
+            .set(migrations::open(files::DB.as_path())?)
+            .expect("OnceCell should be empty");
+
+        Ok(false)
+    }
+}
+
+pub fn create_backup() -> Result<(), Box<dyn Error>> {
+    fs::copy(files::DB.as_path(), files::DB_BACKUP.as_path())?;
+
+    Ok(())
+}
+
+pub fn restore_backup() -> Result<(), Box<dyn Error>> {
+    fs::rename(files::DB_BACKUP.as_path(), files::DB.as_path())?;
+
+    Ok(())
+}
+
+/// Checks whether `cards` and `sets` exist and contain data.
+pub fn tables_intact(connection: &Connection) -> bool {
+    connection
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM cards) AND EXISTS(SELECT 1 FROM sets)",
+            (),
+            |row| row.get(0),
+        )
+        .unwrap_or(false)
+}
+
+const SETS_COLUMNS: [(&str, &str); 4] = [
+    ("set_name", "name"),
+    ("tcg_date", "date"),
+    ("set_code", "code"),
+    ("num_of_cards", "cards"),
+];
+
+const CARDS_COLUMNS: [(&str, &str); 12] = [
+    ("id", "id"),
+    ("name", "name"),
+    ("type", "card_type"),
+    ("desc", "description"),
+    ("atk", "atk"),
+    ("def", "def"),
+    ("level", "level"),
+    ("race", "type"),
+    ("attribute", "attribute"),
+    ("archetype", "archetype"),
+    ("scale", "pend_scale"),
+    ("linkval", "link_rating"),
+];
+
+fn json_columns(columns: &[(&str, &str)]) -> String {
+    columns.iter().fold("".to_string(), |mut prev, new| {
+        let prefix = if prev.is_empty() { "" } else { ", " };
+        prev.push_str(&format!(
+            "{prefix}json_extract(value, '$.{}') as {}",
+            new.0, new.1
+        ));
+        prev
+    })
+}
+
+/// Rebuilds the card tables from `source`.
+///
+/// The connection is expected to be migrated to the current schema (see [`migrations::open`]).
+///
+/// All data is first loaded into temporary staging tables and only replaces the
+/// current tables once it has been validated. Everything happens inside a single
+/// transaction, so on failure the database is left as it was.
+pub fn update(
+    connection: &mut Connection,
+    source: &dyn CardDataSource,
+) -> Result<(), Box<dyn Error>> {
+    update_with_progress(connection, source, &mut |_| (), &AtomicBool::new(false))
+}
+
+/// Same as [`update`], but reports each [`UpdateStage`] to `progress` and returns
+/// [`Cancelled`] as soon as `cancel` is set between two stages or during a download.
+pub fn update_with_progress(
+    connection: &mut Connection,
+    source: &dyn CardDataSource,
+    progress: &mut dyn FnMut(UpdateStage),
+    cancel: &AtomicBool,
+) -> Result<(), Box<dyn Error>> {
+    progress(UpdateStage::CheckingVersion);
+    let version = get_upstream_version(source)?;
+    check_cancelled(cancel)?;
+
+    progress(UpdateStage::DownloadingSets);
+    let sets_json = source.fetch_card_sets(cancel)?;
+    check_cancelled(cancel)?;
+
+    progress(UpdateStage::DownloadingCards);
+    let cards_json = source.fetch_card_info(cancel)?;
+    check_cancelled(cancel)?;
+
+    progress(UpdateStage::BuildingTables);
+    let transaction = connection.transaction()?;
+
+    transaction.execute_batch(
+        "CREATE TEMP TABLE staged_sets AS SELECT * FROM sets WHERE 0;
+        CREATE TEMP TABLE staged_cards AS SELECT * FROM cards WHERE 0;
+        CREATE TEMP TABLE staged_set_contents AS SELECT * FROM set_contents WHERE 0;
+        CREATE TEMP TABLE staged_banlists AS SELECT * FROM banlists WHERE 0;",
+    )?;
+
+    transaction.execute(
+        &format!(
+            "INSERT INTO temp.staged_sets SELECT {} FROM json_each(?1)",
+            json_columns(&SETS_COLUMNS),
+        ),
+        [&sets_json],
+    )?;
+
+    transaction.execute(
+        &format!(
+            "INSERT INTO temp.staged_cards SELECT {} FROM json_each(?1, '$.data')",
+            json_columns(&CARDS_COLUMNS),
+        ),
+        [&cards_json],
+    )?;
+
+    transaction.execute(
+        "INSERT INTO temp.staged_set_contents SELECT card_id,
+                json_extract(value, '$.set_name') as set_name,
+                json_extract(value, '$.set_rarity') as rarity,
+                json_extract(value, '$.set_code') as set_code,
+                json_extract(value, '$.set_rarity_code') as rarity_code,
+                CAST(json_extract(value, '$.set_price') AS REAL) as price
+        FROM (
+            SELECT  json_extract(value, '$.id') as card_id,
+                    json_extract(value, '$.card_sets') as sets
+            FROM json_each(?1, '$.data')
+        ) as flat, json_each(flat.sets)",
+        [&cards_json],
+    )?;
+
+    // `banlist_info` maps each format to a status, e.g. `{"ban_tcg": "Limited"}`.
+    transaction.execute(
+        "INSERT INTO temp.staged_banlists SELECT card_id,
+                substr(ban.key, length('ban_') + 1) as format,
+                ban.value as status
+        FROM (
+            SELECT  json_extract(value, '$.id') as card_id,
+                    json_extract(value, '$.banlist_info') as info
+            FROM json_each(?1, '$.data')
+            WHERE info IS NOT NULL
+        ) as flat, json_each(flat.info) as ban",
+        [&cards_json],
+    )?;
+
+    for table in ["sets", "cards"] {
+        let count: i64 = transaction.query_row(
+            &format!("SELECT COUNT(*) FROM temp.staged_{table}"),
+            (),
+            |row| row.get(0),
+        )?;
+
+        if count == 0 {
+            return Err(format!("Received no data for table `{table}`.").into());
+        }
+    }
+
+    transaction.execute_batch(
+        "DELETE FROM banlists;
+        DELETE FROM set_contents;
+        DELETE FROM sets;
+        DELETE FROM cards;
+        INSERT INTO sets SELECT * FROM temp.staged_sets;
+        INSERT INTO cards SELECT * FROM temp.staged_cards;
+        INSERT INTO set_contents SELECT * FROM temp.staged_set_contents
+            WHERE set_name IN (SELECT name FROM sets);
+        INSERT INTO banlists SELECT * FROM temp.staged_banlists;
+        INSERT INTO cards_fts(cards_fts) VALUES('rebuild');
+        DROP TABLE temp.staged_banlists;
+        DROP TABLE temp.staged_set_contents;
+        DROP TABLE temp.staged_sets;
+        DROP TABLE temp.staged_cards;",
+    )?;
+
+    check_cancelled(cancel)?;
+    transaction.commit()?;
+
+    fs::write(files::DB_VERSION.as_path(), version)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use std::path::{Path, PathBuf};
+
+    use super::*;
+    use crate::db::source::LocalSource;
+
+    /// Payloads of eight cards in three sets, including banlist info and a card without
+    /// printings.
+    pub fn fixture_source() -> LocalSource {
+        LocalSource::new(fixture_dir())
+    }
+
+    pub fn fixture_dir() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/card_data")
+    }
+
+    /// Copy of the fixture payloads with `file_name` replaced by `payload`.
+    pub fn source_with(dir: &Path, file_name: &str, payload: &str) -> LocalSource {
+        for entry in fs::read_dir(fixture_dir()).unwrap() {
+            let path = entry.unwrap().path();
+            fs::copy(&path, dir.join(path.file_name().unwrap())).unwrap();
+        }
+        fs::write(dir.join(file_name), payload).unwrap();
+
+        LocalSource::new(dir.to_path_buf())
+    }
+
+    /// In-memory database built from [`fixture_source`].
+    pub fn fixture_database() -> Connection {
+        let _dirs = crate::data::test_dirs();
+
+        let mut connection = Connection::open_in_memory().unwrap();
+        migrations::migrate(&mut connection).unwrap();
+        update(&mut connection, &fixture_source()).unwrap();
+
+        connection
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::{fixture_database, source_with};
+    use super::*;
+    use crate::data::test_dirs;
+
+    fn rows(connection: &Connection) -> Vec<(String, i64)> {
+        ["cards", "sets", "set_contents", "banlists"]
+            .into_iter()
+            .map(|table| {
+                let count = connection
+                    .query_row(&format!("SELECT COUNT(*) FROM {table}"), (), |row| {
+                        row.get(0)
+                    })
+                    .unwrap();
+                (table.to_string(), count)
+            })
+            .collect()
+    }
+
+    fn staging_tables(connection: &Connection) -> i64 {
+        connection
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_temp_master WHERE name LIKE 'staged_%'",
+                (),
+                |row| row.get(0),
+            )
+            .unwrap()
+    }
+
+    /// Runs a failing update and checks that the database is unchanged.
+    fn assert_update_fails(file_name: &str, payload: &str) {
+        let mut connection = fixture_database();
+        let before = rows(&connection);
+        let dir = tempfile::tempdir().unwrap();
+
+        let _dirs = test_dirs();
+        fs::write(files::DB_VERSION.as_path(), "1.22").unwrap();
+        let result = update(
+            &mut connection,
+            &source_with(dir.path(), file_name, payload),
+        );
+
+        assert!(result.is_err(), "{} = {:?}", file_name, payload);
+        assert_eq!(rows(&connection), before);
+        assert_eq!(staging_tables(&connection), 0);
+        assert!(connection.is_autocommit());
+        assert_eq!(
+            fs::read_to_string(files::DB_VERSION.as_path()).unwrap(),
+            "1.22"
+        );
+    }
+
+    #[test]
+    fn update_builds_tables() {
+        let connection = fixture_database();
+
+        assert_eq!(
+            rows(&connection),
+            [
+                ("cards".to_string(), 8),
+                ("sets".to_string(), 3),
+                ("set_contents".to_string(), 9),
+                ("banlists".to_string(), 4),
+            ]
+        );
+        assert_eq!(staging_tables(&connection), 0);
+        assert!(tables_intact(&connection));
+    }
+
+    #[test]
+    fn empty_payloads_keep_tables() {
+        assert_update_fails("cardsets.json", "[]");
+        assert_update_fails("cardinfo.json", r#"{"data": []}"#);
+    }
+
+    #[test]
+    fn malformed_payloads_keep_tables() {
+        assert_update_fails("cardsets.json", "[{\"set_name\": ");
+        assert_update_fails("cardinfo.json", "<html>Service unavailable</html>");
+        assert_update_fails("checkDBVer.json", "[]");
+    }
+}
@@ -0,0 +1,46 @@
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Stages of a database update, in the order they happen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateStage {
+    CheckingVersion,
+    DownloadingSets,
+    DownloadingCards,
+    BuildingTables,
+    DownloadingImages { done: usize, total: usize },
+}
+
+impl UpdateStage {
+    pub fn description(&self) -> String {
+        match self {
+            UpdateStage::CheckingVersion => "Checking database version…".to_string(),
+            UpdateStage::DownloadingSets => "Downloading sets…".to_string(),
+            UpdateStage::DownloadingCards => "Downloading cards…".to_string(),
+            UpdateStage::BuildingTables => "Building tables…".to_string(),
+            UpdateStage::DownloadingImages { done, total } => {
+                format!("Downloading images ({done}/{total})…")
+            }
+        }
+    }
+}
+
+/// Error returned when an update is cancelled through its cancellation flag.
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The database update was cancelled.")
+    }
+}
+
+impl Error for Cancelled {}
+
+pub fn check_cancelled(cancel: &AtomicBool) -> Result<(), Cancelled> {
+    match cancel.load(Ordering::Relaxed) {
+        true => Err(Cancelled),
+        false => Ok(()),
+    }
+}
@@ -0,0 +1,438 @@
+use std::ops::{Bound, RangeBounds};
+
+use rusqlite::{params_from_iter, types::Value, Connection};
+
+use super::cards::CardInfo;
+use crate::user_data::collection::CardType;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Order {
+    #[default]
+    Name,
+    Id,
+    Atk,
+    Def,
+    Level,
+    /// Best full-text matches first. Falls back to [`Order::Name`] without search text.
+    Relevance,
+}
+
+/// Inclusive bounds for a numeric column.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct StatRange {
+    min: Option<i64>,
+    max: Option<i64>,
+}
+
+impl StatRange {
+    fn from_bounds(range: impl RangeBounds<i64>) -> Self {
+        Self {
+            min: match range.start_bound() {
+                Bound::Included(x) => Some(*x),
+                Bound::Excluded(x) => Some(x + 1),
+                Bound::Unbounded => None,
+            },
+            max: match range.end_bound() {
+                Bound::Included(x) => Some(*x),
+                Bound::Excluded(x) => Some(x - 1),
+                Bound::Unbounded => None,
+            },
+        }
+    }
+}
+
+/// Filtered, ordered and paged query over the `cards` table, shared by every view
+/// that lets the user look for cards.
+#[derive(Debug, Clone, Default)]
+pub struct CardQuery {
+    match_query: Option<String>,
+    attribute: Option<String>,
+    race: Option<String>,
+    card_type: Option<String>,
+    archetype: Option<String>,
+    set_name: Option<String>,
+    ids: Option<Vec<CardType>>,
+    level: StatRange,
+    atk: StatRange,
+    def: StatRange,
+    link_rating: StatRange,
+    pend_scale: StatRange,
+    order: Order,
+    descending: bool,
+    limit: Option<u32>,
+    offset: u64,
+}
+
+impl CardQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Matches all words of `text` against card names and descriptions. Words are
+    /// treated as prefixes, so partially typed words match as well.
+    pub fn text(mut self, text: &str) -> Self {
+        let match_query = fts_match_query(text);
+        self.match_query = (!match_query.is_empty()).then_some(match_query);
+        self
+    }
+
+    pub fn attribute(mut self, attribute: &str) -> Self {
+        self.attribute = Some(attribute.to_string());
+        self
+    }
+
+    /// Filters by monster type (e.g. "Dragon") or spell/trap property (e.g. "Quick-Play").
+    pub fn race(mut self, race: &str) -> Self {
+        self.race = Some(race.to_string());
+        self
+    }
+
+    /// Matches card types containing `card_type`, so "Monster" matches
+    /// "Effect Monster" and "Fusion Monster".
+    pub fn card_type(mut self, card_type: &str) -> Self {
+        self.card_type = Some(card_type.to_string());
+        self
+    }
+
+    pub fn archetype(mut self, archetype: &str) -> Self {
+        self.archetype = Some(archetype.to_string());
+        self
+    }
+
+    /// Only returns cards printed in the set called `set_name`.
+    pub fn set(mut self, set_name: &str) -> Self {
+        self.set_name = Some(set_name.to_string());
+        self
+    }
+
+    /// Only returns cards whose id is in `ids`, e.g. the cards of a collection.
+    pub fn within(mut self, ids: Vec<CardType>) -> Self {
+        self.ids = Some(ids);
+        self
+    }
+
+    pub fn level(mut self, range: impl RangeBounds<i64>) -> Self {
+        self.level = StatRange::from_bounds(range);
+        self
+    }
+
+    pub fn atk(mut self, range: impl RangeBounds<i64>) -> Self {
+        self.atk = StatRange::from_bounds(range);
+        self
+    }
+
+    pub fn def(mut self, range: impl RangeBounds<i64>) -> Self {
+        self.def = StatRange::from_bounds(range);
+        self
+    }
+
+    pub fn link_rating(mut self, range: impl RangeBounds<i64>) -> Self {
+        self.link_rating = StatRange::from_bounds(range);
+        self
+    }
+
+    pub fn pend_scale(mut self, range: impl RangeBounds<i64>) -> Self {
+        self.pend_scale = StatRange::from_bounds(range);
+        self
+    }
+
+    pub fn order_by(mut self, order: Order, descending: bool) -> Self {
+        self.order = order;
+        self.descending = descending;
+        self
+    }
+
+    /// Returns page number `page` (starting at 0) with `page_size` results per page.
+    pub fn page(mut self, page: u32, page_size: u32) -> Self {
+        self.limit = Some(page_size);
+        self.offset = u64::from(page) * u64::from(page_size);
+        self
+    }
+
+    pub fn run(&self, connection: &Connection) -> rusqlite::Result<Vec<CardInfo>> {
+        let (filter, mut params) = self.build_filter();
+
+        let order_column = match self.order {
+            Order::Relevance if self.match_query.is_some() => "cards_fts.rank",
+            Order::Name | Order::Relevance => "cards.name",
+            Order::Id => "cards.id",
+            Order::Atk => "cards.atk",
+            Order::Def => "cards.def",
+            Order::Level => "cards.level",
+        };
+        let direction = if self.descending { "DESC" } else { "ASC" };
+
+        let mut sql = format!(
+            "SELECT {} {filter} ORDER BY {order_column} {direction}, cards.id",
+            CardInfo::COLUMNS
+        );
+
+        if let Some(limit) = self.limit {
+            sql.push_str(" LIMIT ? OFFSET ?");
+            params.push(Value::Integer(limit.into()));
+            params.push(Value::Integer(
+                i64::try_from(self.offset).unwrap_or(i64::MAX),
+            ));
+        }
+
+        connection
+            .prepare(&sql)?
+            .query_map(params_from_iter(params), CardInfo::from_row)?
+            .collect()
+    }
+
+    /// Number of matching cards, ignoring paging.
+    pub fn count(&self, connection: &Connection) -> rusqlite::Result<u32> {
+        let (filter, params) = self.build_filter();
+
+        connection.query_row(
+            &format!("SELECT COUNT(*) {filter}"),
+            params_from_iter(params),
+            |row| row.get(0),
+        )
+    }
+
+    /// Builds the `FROM ... WHERE ...` part of the query and its parameters.
+    fn build_filter(&self) -> (String, Vec<Value>) {
+        let mut sql = "FROM cards".to_string();
+        let mut conditions: Vec<String> = Vec::new();
+        let mut params: Vec<Value> = Vec::new();
+
+        if let Some(match_query) = &self.match_query {
+            sql.push_str(" JOIN cards_fts ON cards_fts.rowid = cards.id");
+            conditions.push("cards_fts MATCH ?".to_string());
+            params.push(Value::Text(match_query.clone()));
+        }
+
+        for (condition, value) in [
+            ("cards.attribute = ? COLLATE NOCASE", &self.attribute),
+            ("cards.type = ? COLLATE NOCASE", &self.race),
+            ("cards.card_type LIKE '%' || ? || '%'", &self.card_type),
+            ("cards.archetype = ? COLLATE NOCASE", &self.archetype),
+            (
+                "cards.id IN (SELECT card_id FROM set_contents WHERE set_name = ?)",
+                &self.set_name,
+            ),
+        ] {
+            if let Some(value) = value {
+                conditions.push(condition.to_string());
+                params.push(Value::Text(value.clone()));
+            }
+        }
+
+        if let Some(ids) = &self.ids {
+            conditions.push("cards.id IN (SELECT value FROM json_each(?))".to_string());
+            params.push(Value::Text(
+                serde_json::to_string(ids).expect("A list of integers is valid JSON"),
+            ));
+        }
+
+        for (column, range) in [
+            ("cards.level", self.level),
+            ("cards.atk", self.atk),
+            ("cards.def", self.def),
+            ("cards.link_rating", self.link_rating),
+            ("cards.pend_scale", self.pend_scale),
+        ] {
+            if let Some(min) = range.min {
+                conditions.push(format!("{column} >= ?"));
+                params.push(Value::Integer(min));
+            }
+            if let Some(max) = range.max {
+                conditions.push(format!("{column} <= ?"));
+                params.push(Value::Integer(max));
+            }
+        }
+
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+
+        (sql, params)
+    }
+}
+
+/// Turns user input into an FTS5 query that matches all words as prefixes.
+///
+/// Every word is quoted so characters like `-` or `"` in card names are not
+/// interpreted as FTS5 syntax.
+fn fts_match_query(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::db::testing::{fixture_database, fixture_dir, source_with};
+    use crate::db::update;
+
+    fn ids(query: &CardQuery, connection: &Connection) -> Vec<CardType> {
+        query
+            .run(connection)
+            .unwrap()
+            .into_iter()
+            .map(|info| info.id)
+            .collect()
+    }
+
+    #[test]
+    fn pages() {
+        let connection = fixture_database();
+        let query = CardQuery::new().order_by(Order::Id, false);
+
+        assert_eq!(
+            ids(&query, &connection),
+            [32864, 1861629, 15025844, 25451652, 40640057, 46986414, 55144522, 89631139]
+        );
+        assert_eq!(
+            ids(&query.clone().page(1, 3), &connection),
+            [25451652, 40640057, 46986414]
+        );
+        assert_eq!(
+            ids(&query.clone().page(2, 3), &connection),
+            [55144522, 89631139]
+        );
+        assert!(ids(&query.clone().page(u32::MAX, u32::MAX), &connection).is_empty());
+        assert_eq!(query.page(1, 3).count(&connection).unwrap(), 8);
+    }
+
+    #[test]
+    fn matches_names_and_descriptions() {
+        let connection = fixture_database();
+        let text = |text| ids(&CardQuery::new().text(text), &connection);
+
+        assert_eq!(text("dragon"), [89631139, 25451652]);
+        assert_eq!(text("wizard"), [46986414, 25451652]);
+        assert_eq!(text("kuri"), [40640057]);
+        assert_eq!(text("dark magi"), [46986414]);
+        assert_eq!(text("destruction dragon"), [89631139]);
+        assert!(text("dragon magician").is_empty());
+        assert_eq!(text("   "), text(""));
+        assert_eq!(text("").len(), 8);
+    }
+
+    #[test]
+    fn escapes_quotes_and_operators() {
+        let connection = fixture_database();
+        let text = |text| ids(&CardQuery::new().text(text), &connection);
+
+        assert_eq!(fts_match_query("a \"b"), "\"a\"* \"\"\"b\"*");
+        assert_eq!(text("Blue-Eyes"), [89631139]);
+        assert_eq!(text("\"Time Wizard\""), [25451652]);
+        assert_eq!(text("'Bob''s"), [1861629]);
+        assert_eq!(text("13th"), [32864]);
+        for input in [
+            "\"",
+            "dragon OR kuriboh",
+            "NOT dragon",
+            "NEAR(dragon",
+            "name:dragon",
+            "*",
+            "^",
+        ] {
+            assert!(
+                CardQuery::new().text(input).run(&connection).is_ok(),
+                "{input}"
+            );
+        }
+        assert!(text("dragon OR kuriboh").is_empty());
+    }
+
+    #[test]
+    fn combines_filters() {
+        let connection = fixture_database();
+        let query = CardQuery::new().order_by(Order::Id, false);
+
+        assert_eq!(
+            ids(
+                &query.clone().attribute("dark").card_type("Monster"),
+                &connection
+            ),
+            [32864, 1861629, 40640057, 46986414]
+        );
+        assert_eq!(
+            ids(
+                &query
+                    .clone()
+                    .attribute("DARK")
+                    .race("spellcaster")
+                    .level(7..),
+                &connection
+            ),
+            [46986414]
+        );
+        assert_eq!(
+            ids(
+                &query.clone().set("Metal Raiders").atk(..=1200),
+                &connection
+            ),
+            [32864, 40640057]
+        );
+        assert_eq!(
+            ids(
+                &query
+                    .clone()
+                    .text("dragon")
+                    .card_type("Fusion")
+                    .def(2000..=2000),
+                &connection
+            ),
+            [25451652]
+        );
+        assert_eq!(
+            ids(
+                &query.clone().archetype("blue-eyes").set("Metal Raiders"),
+                &connection
+            ),
+            Vec::<CardType>::new()
+        );
+        assert_eq!(
+            ids(
+                &query
+                    .clone()
+                    .within(vec![55144522, 1861629, 1])
+                    .link_rating(3..),
+                &connection
+            ),
+            [1861629]
+        );
+        assert_eq!(
+            query
+                .clone()
+                .within(vec![55144522, 89631139])
+                .text("dragon")
+                .count(&connection)
+                .unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn update_rebuilds_index() {
+        let mut connection = fixture_database();
+        let dir = tempfile::tempdir().unwrap();
+        let cards = fs::read_to_string(fixture_dir().join("cardinfo.json"))
+            .unwrap()
+            .replace("\"name\":\"Kuriboh\"", "\"name\":\"Winged Fluffball\"");
+
+        {
+            let _dirs = crate::data::test_dirs();
+            update(
+                &mut connection,
+                &source_with(dir.path(), "cardinfo.json", &cards),
+            )
+            .unwrap();
+        }
+
+        let text = |text| ids(&CardQuery::new().text(text), &connection);
+        assert_eq!(text("fluffball"), [40640057]);
+        assert!(text("kuriboh").is_empty());
+        assert_eq!(text("dragon"), [89631139, 25451652]);
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+
+use rusqlite::{Connection, OptionalExtension, Row};
+
+use crate::user_data::collection::CardType;
+
+/// A single row of the `sets` table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetInfo {
+    pub name: String,
+    /// TCG release date in `YYYY-MM-DD` format.
+    pub date: Option<String>,
+    pub code: Option<String>,
+    pub cards: Option<u32>,
+}
+
+impl SetInfo {
+    pub const COLUMNS: &'static str = "sets.name, sets.date, sets.code, sets.cards";
+
+    pub fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            name: row.get(0)?,
+            date: row.get(1)?,
+            code: row.get(2)?,
+            cards: row.get(3)?,
+        })
+    }
+}
+
+/// A single printing of a card, i.e. a row of the `set_contents` table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Printing {
+    pub card_id: CardType,
+    pub set_name: String,
+    /// Set number of the printing, e.g. `LOB-EN001`.
+    pub set_code: Option<String>,
+    pub rarity: Option<String>,
+    /// Abbreviated rarity, e.g. `(UR)`.
+    pub rarity_code: Option<String>,
+    pub price: Option<f64>,
+}
+
+impl Printing {
+    pub const COLUMNS: &'static str = "set_contents.card_id, set_contents.set_name, \
+        set_contents.set_code, set_contents.rarity, set_contents.rarity_code, set_contents.price";
+
+    pub fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            card_id: row.get(0)?,
+            set_name: row.get(1)?,
+            set_code: row.get(2)?,
+            rarity: row.get(3)?,
+            rarity_code: row.get(4)?,
+            price: row.get(5)?,
+        })
+    }
+}
+
+pub fn get(connection: &Connection, name: &str) -> rusqlite::Result<Option<SetInfo>> {
+    connection
+        .query_row(
+            &format!("SELECT {} FROM sets WHERE name = ?1", SetInfo::COLUMNS),
+            [name],
+            SetInfo::from_row,
+        )
+        .optional()
+}
+
+/// Returns all sets in release order. Sets without a release date come last.
+pub fn get_all(connection: &Connection) -> rusqlite::Result<Vec<SetInfo>> {
+    connection
+        .prepare_cached(&format!(
+            "SELECT {} FROM sets ORDER BY date IS NULL, date, name",
+            SetInfo::COLUMNS
+        ))?
+        .query_map((), SetInfo::from_row)?
+        .collect()
+}
+
+/// Returns every printing of the card with `card_id`, oldest first.
+pub fn get_printings(
+    connection: &Connection,
+    card_id: CardType,
+) -> rusqlite::Result<Vec<Printing>> {
+    connection
+        .prepare_cached(&format!(
+            "SELECT {} FROM set_contents
+            JOIN sets ON sets.name = set_contents.set_name
+            WHERE set_contents.card_id = ?1
+            ORDER BY sets.date IS NULL, sets.date, set_contents.set_code",
+            Printing::COLUMNS
+        ))?
+        .query_map([card_id], Printing::from_row)?
+        .collect()
+}
+
+/// Returns the oldest printing of each card in `ids` in a single query. Cards without
+/// printings are missing from the result.
+pub fn get_first_printings(
+    connection: &Connection,
+    ids: &[CardType],
+) -> rusqlite::Result<HashMap<CardType, Printing>> {
+    let ids_json = serde_json::to_string(ids).expect("A list of integers is valid JSON");
+    let mut printings = HashMap::new();
+
+    let mut statement = connection.prepare_cached(&format!(
+        "SELECT {} FROM set_contents
+        JOIN sets ON sets.name = set_contents.set_name
+        WHERE set_contents.card_id IN (SELECT value FROM json_each(?1))
+        ORDER BY sets.date IS NULL, sets.date, set_contents.set_code",
+        Printing::COLUMNS
+    ))?;
+    for printing in statement.query_map([ids_json], Printing::from_row)? {
+        let printing = printing?;
+        printings.entry(printing.card_id).or_insert(printing);
+    }
+
+    Ok(printings)
+}
+
+/// Returns all printings in the set called `set_name`, sorted by set number.
+pub fn get_checklist(connection: &Connection, set_name: &str) -> rusqlite::Result<Vec<Printing>> {
+    connection
+        .prepare_cached(&format!(
+            "SELECT {} FROM set_contents
+            WHERE set_contents.set_name = ?1
+            ORDER BY set_contents.set_code, set_contents.card_id",
+            Printing::COLUMNS
+        ))?
+        .query_map([set_name], Printing::from_row)?
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::testing::fixture_database;
+
+    const LOB: &str = "Legend of Blue Eyes White Dragon";
+
+    #[test]
+    fn sets_in_release_order() {
+        let connection = fixture_database();
+
+        assert_eq!(
+            get(&connection, "Metal Raiders").unwrap(),
+            Some(SetInfo {
+                name: "Metal Raiders".to_string(),
+                date: Some("2002-06-26".to_string()),
+                code: Some("MRD".to_string()),
+                cards: Some(144),
+            })
+        );
+        assert_eq!(get(&connection, "Unknown Set").unwrap(), None);
+        assert_eq!(
+            get_all(&connection)
+                .unwrap()
+                .into_iter()
+                .map(|set| set.code.unwrap())
+                .collect::<Vec<_>>(),
+            ["LOB", "SDY", "MRD"]
+        );
+    }
+
+    #[test]
+    fn printings() {
+        let connection = fixture_database();
+
+        assert_eq!(
+            get_printings(&connection, 89631139).unwrap(),
+            [Printing {
+                card_id: 89631139,
+                set_name: LOB.to_string(),
+                set_code: Some("LOB-EN001".to_string()),
+                rarity: Some("Ultra Rare".to_string()),
+                rarity_code: Some("(UR)".to_string()),
+                price: Some(50.0),
+            }]
+        );
+        assert_eq!(
+            get_printings(&connection, 40640057)
+                .unwrap()
+                .into_iter()
+                .map(|printing| (printing.set_code.unwrap(), printing.rarity_code.unwrap()))
+                .collect::<Vec<_>>(),
+            [
+                ("SDY-011".to_string(), "(C)".to_string()),
+                ("MRD-EN071".to_string(), "(SR)".to_string()),
+            ]
+        );
+        assert!(get_printings(&connection, 1861629).unwrap().is_empty());
+    }
+
+    #[test]
+    fn first_printings() {
+        let connection = fixture_database();
+        let ids = [40640057, 46986414, 89631139, 1861629, 999];
+
+        let printings = get_first_printings(&connection, &ids).unwrap();
+        assert_eq!(printings.len(), 3);
+        for id in ids {
+            assert_eq!(
+                printings.get(&id),
+                get_printings(&connection, id).unwrap().first(),
+                "card {id}"
+            );
+        }
+        assert_eq!(printings[&40640057].set_code.as_deref(), Some("SDY-011"));
+        assert_eq!(printings[&46986414].set_code.as_deref(), Some("LOB-EN005"));
+    }
+
+    #[test]
+    fn checklist() {
+        let connection = fixture_database();
+
+        assert_eq!(
+            get_checklist(&connection, LOB)
+                .unwrap()
+                .into_iter()
+                .map(|printing| (printing.card_id, printing.set_code.unwrap()))
+                .collect::<Vec<_>>(),
+            [
+                (89631139, "LOB-EN001".to_string()),
+                (15025844, "LOB-EN002".to_string()),
+                (46986414, "LOB-EN005".to_string()),
+                (55144522, "LOB-EN119".to_string()),
+            ]
+        );
+        assert_eq!(
+            get_checklist(&connection, "Metal Raiders")
+                .unwrap()
+                .into_iter()
+                .map(|printing| (printing.rarity.unwrap(), printing.price.unwrap()))
+                .collect::<Vec<_>>(),
+            [
+                ("Common".to_string(), 0.2),
+                ("Super Rare".to_string(), 3.0),
+                ("Secret Rare".to_string(), 30.0),
+            ]
+        );
+        assert!(get_checklist(&connection, "Unknown Set")
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,211 @@
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+
+use crate::db::check_cancelled;
+
+mod urls {
+    pub const API_CARDINFO: &str = "https://db.ygoprodeck.com/api/v7/cardinfo.php";
+    pub const API_CARDSETS: &str = "https://db.ygoprodeck.com/api/v7/cardsets.php";
+    pub const API_VERSION: &str = "https://db.ygoprodeck.com/api/v7/checkDBVer.php";
+}
+
+mod file_names {
+    pub const CARDINFO: &str = "cardinfo.json";
+    pub const CARDSETS: &str = "cardsets.json";
+    pub const VERSION: &str = "checkDBVer.json";
+}
+
+/// Environment variable that points to a directory to be used with [`LocalSource`].
+pub const LOCAL_SOURCE_ENV: &str = "YGO_DESTINY_DATA_DIR";
+
+/// Provider of the raw JSON payloads used to build the card database.
+///
+/// Every method returns the payload exactly as the YGOPRODeck API would. The large
+/// payloads should stop loading with [`Cancelled`](crate::db::Cancelled) once `cancel`
+/// is set.
+pub trait CardDataSource: Send + Sync {
+    fn fetch_version(&self) -> Result<String, Box<dyn Error>>;
+    fn fetch_card_sets(&self, cancel: &AtomicBool) -> Result<String, Box<dyn Error>>;
+    fn fetch_card_info(&self, cancel: &AtomicBool) -> Result<String, Box<dyn Error>>;
+}
+
+/// Fetches card data from the YGOPRODeck API.
+#[derive(Debug, Default)]
+pub struct ApiSource;
+
+impl ApiSource {
+    fn get(url: &str, cancel: &AtomicBool) -> Result<String, Box<dyn Error>> {
+        read_cancellable(reqwest::blocking::get(url)?.error_for_status()?, cancel)
+    }
+}
+
+/// Reads `reader` to the end, checking `cancel` between chunks.
+fn read_cancellable(mut reader: impl Read, cancel: &AtomicBool) -> Result<String, Box<dyn Error>> {
+    let mut bytes = Vec::new();
+    let mut buffer = [0; 64 * 1024];
+
+    loop {
+        check_cancelled(cancel)?;
+        match reader.read(&mut buffer)? {
+            0 => break,
+            read => bytes.extend_from_slice(&buffer[..read]),
+        }
+    }
+
+    Ok(String::from_utf8(bytes)?)
+}
+
+impl CardDataSource for ApiSource {
+    fn fetch_version(&self) -> Result<String, Box<dyn Error>> {
+        Self::get(urls::API_VERSION, &AtomicBool::new(false))
+    }
+
+    fn fetch_card_sets(&self, cancel: &AtomicBool) -> Result<String, Box<dyn Error>> {
+        Self::get(urls::API_CARDSETS, cancel)
+    }
+
+    fn fetch_card_info(&self, cancel: &AtomicBool) -> Result<String, Box<dyn Error>> {
+        Self::get(urls::API_CARDINFO, cancel)
+    }
+}
+
+/// Reads card data from a directory containing `checkDBVer.json`, `cardsets.json` and
+/// `cardinfo.json`, as saved from the corresponding API endpoints.
+#[derive(Debug)]
+pub struct LocalSource {
+    dir: PathBuf,
+}
+
+impl LocalSource {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn read(&self, file_name: &str) -> Result<String, Box<dyn Error>> {
+        Ok(fs::read_to_string(self.dir.join(file_name))?)
+    }
+}
+
+impl CardDataSource for LocalSource {
+    fn fetch_version(&self) -> Result<String, Box<dyn Error>> {
+        self.read(file_names::VERSION)
+    }
+
+    fn fetch_card_sets(&self, _cancel: &AtomicBool) -> Result<String, Box<dyn Error>> {
+        self.read(file_names::CARDSETS)
+    }
+
+    fn fetch_card_info(&self, _cancel: &AtomicBool) -> Result<String, Box<dyn Error>> {
+        self.read(file_names::CARDINFO)
+    }
+}
+
+/// Returns a [`LocalSource`] if [`LOCAL_SOURCE_ENV`] is set and an [`ApiSource`] otherwise.
+pub fn from_env() -> Box<dyn CardDataSource> {
+    match env::var_os(LOCAL_SOURCE_ENV) {
+        Some(dir) => Box::new(LocalSource::new(PathBuf::from(dir))),
+        None => Box::new(ApiSource),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::Connection;
+
+    use super::*;
+    use crate::data::{files, test_dirs};
+    use crate::db::testing::fixture_source;
+    use crate::db::{get_upstream_version, migrations, new_version_available, update, Cancelled};
+
+    #[test]
+    fn reads_payloads_from_directory() {
+        let source = fixture_source();
+
+        assert!(source.fetch_version().unwrap().contains("database_version"));
+        let cancel = AtomicBool::new(false);
+        assert!(source.fetch_card_sets(&cancel).unwrap().starts_with('['));
+        assert!(source
+            .fetch_card_info(&cancel)
+            .unwrap()
+            .contains("\"data\""));
+        assert!(LocalSource::new(PathBuf::from("/nonexistent"))
+            .fetch_version()
+            .is_err());
+    }
+
+    #[test]
+    fn reads_until_cancelled() {
+        let payload = "x".repeat(200 * 1024);
+        assert_eq!(
+            read_cancellable(payload.as_bytes(), &AtomicBool::new(false)).unwrap(),
+            payload
+        );
+        assert!(read_cancellable(payload.as_bytes(), &AtomicBool::new(true))
+            .is_err_and(|error| error.is::<Cancelled>()));
+        assert!(read_cancellable(&[0xFF, 0xFE][..], &AtomicBool::new(false)).is_err());
+    }
+
+    /// Sets the cancellation flag after the first chunk has been read.
+    struct CancelAfterRead<'a>(&'a [u8], &'a AtomicBool);
+
+    impl Read for CancelAfterRead<'_> {
+        fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+            self.1.store(true, std::sync::atomic::Ordering::Relaxed);
+            self.0.read(buffer)
+        }
+    }
+
+    #[test]
+    fn cancels_between_chunks() {
+        let payload = vec![b'x'; 200 * 1024];
+        let cancel = AtomicBool::new(false);
+
+        assert!(
+            read_cancellable(CancelAfterRead(&payload, &cancel), &cancel)
+                .is_err_and(|error| error.is::<Cancelled>())
+        );
+    }
+
+    #[test]
+    fn upstream_version() {
+        assert_eq!(get_upstream_version(&fixture_source()).unwrap(), "1.23");
+
+        let dir = tempfile::tempdir().unwrap();
+        let source = LocalSource::new(dir.path().to_path_buf());
+        for payload in ["[]", "{}", ""] {
+            fs::write(dir.path().join(file_names::VERSION), payload).unwrap();
+            assert!(get_upstream_version(&source).is_err(), "{:?}", payload);
+        }
+    }
+
+    #[test]
+    fn update_and_version_check() {
+        let _dirs = test_dirs();
+        let source = fixture_source();
+        let _ = fs::remove_file(files::DB_VERSION.as_path());
+        assert!(new_version_available(&source).unwrap());
+
+        let mut connection = Connection::open_in_memory().unwrap();
+        migrations::migrate(&mut connection).unwrap();
+        update(&mut connection, &source).unwrap();
+
+        let count = |table: &str| -> i64 {
+            connection
+                .query_row(&format!("SELECT COUNT(*) FROM {table}"), (), |row| {
+                    row.get(0)
+                })
+                .unwrap()
+        };
+        assert_eq!(count("cards"), 8);
+        assert_eq!(count("sets"), 3);
+        assert_eq!(count("set_contents"), 9);
+        assert!(!new_version_available(&source).unwrap());
+
+        fs::write(files::DB_VERSION.as_path(), "1.22").unwrap();
+        assert!(new_version_available(&source).unwrap());
+    }
+}
@@ -0,0 +1,51 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum DeckFormatError {
+    Io(io::Error),
+    Sqlite(rusqlite::Error),
+    /// A line of a `.ydk` file that is neither a section header, a comment nor a card id.
+    InvalidLine {
+        line: usize,
+        content: String,
+    },
+    /// Not a `ydke://` URL with a main, extra and side deck.
+    InvalidYdke,
+    Base64(base64::DecodeError),
+}
+
+impl fmt::Display for DeckFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "Failed to access deck file: {error}"),
+            Self::Sqlite(error) => write!(f, "Failed to look up deck cards: {error}"),
+            Self::InvalidLine { line, content } => {
+                write!(f, "Line {line} is not a card id: `{content}`")
+            }
+            Self::InvalidYdke => write!(f, "Not a valid `ydke://` deck."),
+            Self::Base64(error) => write!(f, "Deck is not valid base64: {error}"),
+        }
+    }
+}
+
+impl Error for DeckFormatError {}
+
+impl From<io::Error> for DeckFormatError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<rusqlite::Error> for DeckFormatError {
+    fn from(error: rusqlite::Error) -> Self {
+        Self::Sqlite(error)
+    }
+}
+
+impl From<base64::DecodeError> for DeckFormatError {
+    fn from(error: base64::DecodeError) -> Self {
+        Self::Base64(error)
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+
+use rusqlite::Connection;
+
+use crate::db::cards::{self, CardInfo};
+use crate::legality::{validate_with_infos, Violation};
+use crate::user_data::collection::{Card, CardType, Deck, DeckSection};
+
+/// A deck read from a file, checked against the `cards` table.
+#[derive(Debug, Clone)]
+pub struct DeckImport {
+    /// The deck without the cards in `unknown`.
+    pub deck: Deck,
+    /// Info of every card in `deck`.
+    pub infos: HashMap<CardType, CardInfo>,
+    /// Ids that are not in the `cards` table, without duplicates.
+    pub unknown: Vec<CardType>,
+    /// [`Violation::NotOwned`] for every card the collection does not have enough copies
+    /// of. Only checked if the import was given the owned cards.
+    pub not_owned: Vec<Violation>,
+}
+
+impl DeckImport {
+    /// Removes cards that are not in the `cards` table from `deck` and, given the cards
+    /// `owned` by a collection, lists the ones it is missing.
+    pub fn resolve(
+        connection: &Connection,
+        mut deck: Deck,
+        owned: Option<&HashMap<Card, u8>>,
+    ) -> rusqlite::Result<Self> {
+        let ids: Vec<CardType> = DeckSection::ALL
+            .iter()
+            .flat_map(|section| deck.section(*section).iter().copied())
+            .collect();
+        let infos = cards::get_many(connection, &ids)?;
+
+        let mut unknown = Vec::new();
+        for id in ids {
+            if !infos.contains_key(&id) && !unknown.contains(&id) {
+                unknown.push(id);
+            }
+        }
+        for section in DeckSection::ALL {
+            deck.section_mut(section)
+                .retain(|id| infos.contains_key(id));
+        }
+
+        let not_owned = match owned {
+            Some(owned) => validate_with_infos(&deck, &infos, &HashMap::new(), Some(owned))
+                .into_iter()
+                .filter(|violation| matches!(violation, Violation::NotOwned { .. }))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            deck,
+            infos,
+            unknown,
+            not_owned,
+        })
+    }
+}
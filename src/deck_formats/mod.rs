@@ -0,0 +1,9 @@
+mod error;
+mod import;
+mod ydk;
+mod ydke;
+
+pub use error::*;
+pub use import::*;
+pub use ydk::*;
+pub use ydke::*;
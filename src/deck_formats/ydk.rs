@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use rusqlite::Connection;
+
+use super::{DeckFormatError, DeckImport};
+use crate::user_data::collection::{Card, CardType, Deck, DeckSection};
+
+pub const YDK_EXTENSION: &str = "ydk";
+
+const MAIN_HEADER: &str = "#main";
+const EXTRA_HEADER: &str = "#extra";
+const SIDE_HEADER: &str = "!side";
+
+/// Writes `deck` in the `.ydk` format used by EDOPro and YGOPro.
+pub fn to_ydk(deck: &Deck) -> String {
+    let mut text = format!("#created by {}\n", env!("CARGO_PKG_NAME"));
+
+    for section in DeckSection::ALL {
+        text.push_str(header(section));
+        text.push('\n');
+        for id in deck.section(section) {
+            text.push_str(&id.to_string());
+            text.push('\n');
+        }
+    }
+
+    text
+}
+
+/// Parses a `.ydk` file into a deck called `name`. Comments and anything before the
+/// first section header are ignored.
+pub fn parse_ydk(name: String, text: &str) -> Result<Deck, DeckFormatError> {
+    let mut deck = Deck::new(name);
+    let mut section = None;
+
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+
+        if let Some(header_section) = DeckSection::ALL
+            .into_iter()
+            .find(|section| header(*section) == line)
+        {
+            section = Some(header_section);
+            continue;
+        }
+        if line.is_empty() || line.starts_with('#') || line.starts_with('!') {
+            continue;
+        }
+        let Some(section) = section else {
+            continue;
+        };
+
+        // Some tools put a comment after the id.
+        let id = line
+            .split_whitespace()
+            .next()
+            .and_then(|id| id.parse::<CardType>().ok())
+            .ok_or_else(|| DeckFormatError::InvalidLine {
+                line: index + 1,
+                content: line.to_string(),
+            })?;
+        deck.section_mut(section).push(id);
+    }
+
+    Ok(deck)
+}
+
+/// Reads a `.ydk` file, naming the deck after the file.
+pub fn read_ydk(path: &Path) -> Result<Deck, DeckFormatError> {
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    parse_ydk(name, &fs::read_to_string(path)?)
+}
+
+pub fn write_ydk(deck: &Deck, path: &Path) -> Result<(), DeckFormatError> {
+    fs::write(path, to_ydk(deck))?;
+
+    Ok(())
+}
+
+/// Reads a `.ydk` file and resolves its cards, see [`DeckImport::resolve`].
+pub fn import_ydk(
+    connection: &Connection,
+    path: &Path,
+    owned: Option<&HashMap<Card, u8>>,
+) -> Result<DeckImport, DeckFormatError> {
+    Ok(DeckImport::resolve(connection, read_ydk(path)?, owned)?)
+}
+
+fn header(section: DeckSection) -> &'static str {
+    match section {
+        DeckSection::Main => MAIN_HEADER,
+        DeckSection::Extra => EXTRA_HEADER,
+        DeckSection::Side => SIDE_HEADER,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations;
+    use crate::legality::Violation;
+
+    const BLUE_EYES: CardType = 89631139;
+    const TWIN_BURST: CardType = 2129638;
+    const UNKNOWN: CardType = 12345;
+
+    fn database() -> Connection {
+        let mut connection = Connection::open_in_memory().unwrap();
+        migrations::migrate(&mut connection).unwrap();
+        connection
+            .execute_batch(&format!(
+                "INSERT INTO cards (id, name, card_type, description) VALUES
+                ({BLUE_EYES}, 'Blue-Eyes White Dragon', 'Normal Monster', ''),
+                ({TWIN_BURST}, 'Blue-Eyes Twin Burst Dragon', 'Fusion Monster', '');"
+            ))
+            .unwrap();
+
+        connection
+    }
+
+    fn ydk_file(name: &str, text: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "ygo_destiny_{}_{}.{}",
+            name,
+            std::process::id(),
+            YDK_EXTENSION
+        ));
+        fs::write(&path, text).unwrap();
+        path
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut deck = Deck::new("Dragons".to_string());
+        deck.main = vec![BLUE_EYES, BLUE_EYES];
+        deck.extra = vec![TWIN_BURST];
+        deck.side = vec![BLUE_EYES];
+
+        assert_eq!(
+            parse_ydk("Dragons".to_string(), &to_ydk(&deck)).unwrap(),
+            deck
+        );
+    }
+
+    #[test]
+    fn reports_invalid_lines() {
+        let result = parse_ydk(String::new(), "#main\n89631139\nnot an id\n");
+
+        assert!(matches!(
+            result,
+            Err(DeckFormatError::InvalidLine { line: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn import_checks_owned_cards() {
+        let connection = database();
+        let path = ydk_file(
+            "import",
+            &format!("#main\n{BLUE_EYES}\n{BLUE_EYES}\n{UNKNOWN}\n#extra\n{TWIN_BURST}\n!side\n"),
+        );
+        let owned = HashMap::from([(Card { id: BLUE_EYES }, 1), (Card { id: TWIN_BURST }, 1)]);
+
+        let unchecked = import_ydk(&connection, &path, None).unwrap();
+        assert_eq!(
+            unchecked.deck.name,
+            path.file_stem().unwrap().to_string_lossy()
+        );
+        assert_eq!(unchecked.deck.main, [BLUE_EYES, BLUE_EYES]);
+        assert_eq!(unchecked.unknown, [UNKNOWN]);
+        assert!(unchecked.not_owned.is_empty());
+
+        let checked = import_ydk(&connection, &path, Some(&owned)).unwrap();
+        assert_eq!(
+            checked.not_owned,
+            [Violation::NotOwned {
+                card: BLUE_EYES,
+                copies: 2,
+                owned: 1,
+            }]
+        );
+
+        fs::remove_file(path).unwrap();
+    }
+}
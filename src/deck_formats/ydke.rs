@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use super::DeckFormatError;
+use crate::user_data::collection::{Card, CardType, Collection, Deck, DeckSection};
+
+pub const YDKE_PREFIX: &str = "ydke://";
+
+/// Encodes `deck` as a `ydke://` URL: the main, extra and side deck as base64 of
+/// little-endian card ids, each followed by `!`.
+pub fn to_ydke(deck: &Deck) -> String {
+    let mut url = YDKE_PREFIX.to_string();
+
+    for section in DeckSection::ALL {
+        let bytes: Vec<u8> = deck
+            .section(section)
+            .iter()
+            .flat_map(|id| id.to_le_bytes())
+            .collect();
+        url.push_str(&STANDARD.encode(bytes));
+        url.push('!');
+    }
+
+    url
+}
+
+/// Decodes a `ydke://` URL into a deck called `name`. The trailing `!` is optional.
+pub fn parse_ydke(name: String, url: &str) -> Result<Deck, DeckFormatError> {
+    let sections = url
+        .trim()
+        .strip_prefix(YDKE_PREFIX)
+        .ok_or(DeckFormatError::InvalidYdke)?;
+
+    let mut sections: Vec<&str> = sections.split('!').collect();
+    if sections.len() == 4 && sections[3].is_empty() {
+        sections.pop();
+    }
+    let [main, extra, side] = sections[..] else {
+        return Err(DeckFormatError::InvalidYdke);
+    };
+
+    Ok(Deck {
+        name,
+        main: decode_section(main)?,
+        extra: decode_section(extra)?,
+        side: decode_section(side)?,
+    })
+}
+
+/// Encodes the card pool of `collection` as a `ydke://` URL with every copy in the
+/// main deck.
+pub fn collection_to_ydke(collection: &Collection) -> String {
+    let mut cards: Vec<(CardType, u8)> = collection
+        .cards
+        .iter()
+        .map(|(card, quantity)| (card.id, *quantity))
+        .collect();
+    cards.sort();
+
+    let deck = Deck {
+        main: cards
+            .into_iter()
+            .flat_map(|(id, quantity)| vec![id; quantity as usize])
+            .collect(),
+        ..Default::default()
+    };
+
+    to_ydke(&deck)
+}
+
+/// Decodes a `ydke://` URL into a card pool, counting the copies of all sections.
+pub fn ydke_to_cards(url: &str) -> Result<HashMap<Card, u8>, DeckFormatError> {
+    let deck = parse_ydke(String::new(), url)?;
+
+    let mut cards: HashMap<Card, u8> = HashMap::new();
+    for section in DeckSection::ALL {
+        for id in deck.section(section) {
+            let quantity = cards.entry(Card { id: *id }).or_default();
+            *quantity = quantity.saturating_add(1);
+        }
+    }
+
+    Ok(cards)
+}
+
+fn decode_section(section: &str) -> Result<Vec<CardType>, DeckFormatError> {
+    let bytes = STANDARD.decode(section)?;
+    if bytes.len() % 4 != 0 {
+        return Err(DeckFormatError::InvalidYdke);
+    }
+
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|id| CardType::from_le_bytes([id[0], id[1], id[2], id[3]]))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::user_data::collection::MetaData;
+
+    const BLUE_EYES: CardType = 89631139;
+    const STARDUST: CardType = 44508094;
+    /// Blue-Eyes White Dragon twice in the main deck and Stardust Dragon in the extra
+    /// deck, as exported by EDOPro.
+    const REFERENCE: &str = "ydke://o6lXBaOpVwU=!viOnAg==!!";
+
+    fn reference_deck() -> Deck {
+        Deck {
+            name: "Reference".to_string(),
+            main: vec![BLUE_EYES, BLUE_EYES],
+            extra: vec![STARDUST],
+            side: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn matches_reference() {
+        assert_eq!(to_ydke(&reference_deck()), REFERENCE);
+        assert_eq!(
+            parse_ydke("Reference".to_string(), REFERENCE).unwrap(),
+            reference_deck()
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut deck = reference_deck();
+        deck.side = vec![STARDUST, 1];
+
+        assert_eq!(
+            parse_ydke(deck.name.clone(), &to_ydke(&deck)).unwrap(),
+            deck
+        );
+        assert_eq!(
+            parse_ydke(String::new(), "ydke://!!!").unwrap(),
+            Deck::default()
+        );
+    }
+
+    #[test]
+    fn trailing_separator_is_optional() {
+        assert_eq!(
+            parse_ydke(
+                "Reference".to_string(),
+                REFERENCE.strip_suffix('!').unwrap()
+            )
+            .unwrap(),
+            reference_deck()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_urls() {
+        for url in [
+            "o6lXBaOpVwU=!viOnAg==!!",
+            "ydke://o6lXBaOpVwU=!viOnAg==",
+            "ydke://o6lXBaOpVwU=!!!!",
+            "ydke://o6lXBa!!!",
+            "ydke://not base64!!!",
+        ] {
+            assert!(parse_ydke(String::new(), url).is_err(), "{}", url);
+        }
+    }
+
+    #[test]
+    fn card_pool() {
+        let mut collection = Collection::new(MetaData::default(), 0);
+        collection.cards.insert(Card { id: 2 }, 2);
+        collection.cards.insert(Card { id: 1 }, 1);
+
+        let url = collection_to_ydke(&collection);
+        assert_eq!(url, "ydke://AQAAAAIAAAACAAAA!!!");
+        assert_eq!(ydke_to_cards(&url).unwrap(), collection.cards);
+    }
+
+    #[test]
+    fn card_pool_counts_every_section() {
+        assert_eq!(
+            ydke_to_cards(REFERENCE).unwrap(),
+            HashMap::from([(Card { id: BLUE_EYES }, 2), (Card { id: STARDUST }, 1)])
+        );
+    }
+}
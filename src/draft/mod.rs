@@ -0,0 +1,8 @@
+mod offers;
+pub use offers::*;
+
+mod packs;
+pub use packs::*;
+
+mod rng;
+pub use rng::*;
@@ -0,0 +1,385 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+
+use rand::prelude::*;
+use rusqlite::Connection;
+
+use super::{generate_packs, round_rng, to_ids, PackLayout, RngPurpose, SetPool};
+use crate::user_data::collection::{
+    Card, CardType, Change, Collection, DraftFormat, DuplicatePolicy, PoolSource,
+};
+
+/// Generates offer number `offer` of `round` according to the collection's
+/// [`DraftFormat`], ready to be passed to `DraftContainer`.
+///
+/// Packs come from one of `new_sets`, while single cards can be any card printed in
+/// one of `available_sets`.
+pub fn generate_offer(
+    connection: &Connection,
+    collection: &Collection,
+    round: u16,
+    offer: u16,
+    new_sets: &[String],
+    available_sets: &[String],
+) -> Result<Vec<Vec<CardType>>, Box<dyn Error>> {
+    let format = &collection.draft_format;
+    let mut rng = round_rng(collection.seed, round, RngPurpose::DraftOffer(offer));
+    let excluded = match format.duplicates {
+        DuplicatePolicy::ExcludeOwned => owned_at_start(collection, round),
+        _ => HashSet::new(),
+    };
+
+    match &format.pool {
+        PoolSource::Sets => {
+            let Some(set_name) = new_sets.get(offer as usize % new_sets.len().max(1)) else {
+                return Err(format!("No sets are available in round {}.", round).into());
+            };
+
+            let pool = SetPool::load(connection, set_name)?;
+            offer_packs(pool, format, &excluded, &mut rng)
+        }
+        PoolSource::Archetypes(archetypes) => {
+            let pool = load_singles(connection, available_sets, Some(archetypes))?;
+            offer_singles(pool, format, &excluded, &mut rng)
+        }
+        PoolSource::CardPool => {
+            let pool = load_singles(connection, available_sets, None)?;
+            offer_singles(pool, format, &excluded, &mut rng)
+        }
+    }
+}
+
+/// Cards that were owned when `round` started. Offers are regenerated after every pick,
+/// so the picks of the round itself must not change them.
+fn owned_at_start(collection: &Collection, round: u16) -> HashSet<CardType> {
+    let mut quantities: HashMap<CardType, i32> = collection
+        .cards
+        .iter()
+        .map(|(card, quantity)| (card.id, *quantity as i32))
+        .collect();
+
+    for change in &collection.changes {
+        if let Change::Add(content) = change {
+            if content.round == Some(round) {
+                for card in &content.cards {
+                    *quantities.entry(card.id).or_default() -= 1;
+                }
+            }
+        }
+    }
+
+    quantities
+        .into_iter()
+        .filter(|(_, quantity)| *quantity > 0)
+        .map(|(id, _)| id)
+        .collect()
+}
+
+fn offer_packs<R: Rng + ?Sized>(
+    mut pool: SetPool,
+    format: &DraftFormat,
+    excluded: &HashSet<CardType>,
+    rng: &mut R,
+) -> Result<Vec<Vec<CardType>>, Box<dyn Error>> {
+    if pool.is_empty() {
+        return Err(format!(
+            "Set `{}` has no cards to generate packs from.",
+            pool.set_name
+        )
+        .into());
+    }
+
+    // Restrictions are only applied as long as they leave cards to choose from.
+    let unowned = pool.without(|id| excluded.contains(&id));
+    if !unowned.is_empty() {
+        pool = unowned;
+    }
+
+    let layout = PackLayout::sized(format.cards_per_box);
+    let mut boxes = Vec::with_capacity(format.boxes_per_offer);
+
+    for _ in 0..format.boxes_per_offer {
+        let pack = to_ids(&pool.open_pack(&layout, rng));
+
+        if format.duplicates == DuplicatePolicy::UniqueInOffer {
+            let remaining = pool.without(|id| pack.contains(&id));
+            if !remaining.is_empty() {
+                pool = remaining;
+            }
+        }
+
+        boxes.push(pack);
+    }
+
+    Ok(boxes)
+}
+
+fn offer_singles<R: Rng + ?Sized>(
+    mut pool: Vec<CardType>,
+    format: &DraftFormat,
+    excluded: &HashSet<CardType>,
+    rng: &mut R,
+) -> Result<Vec<Vec<CardType>>, Box<dyn Error>> {
+    if pool.iter().any(|id| !excluded.contains(id)) {
+        pool.retain(|id| !excluded.contains(id));
+    }
+
+    if pool.is_empty() {
+        return Err("The card pool of this draft format is empty.".into());
+    }
+
+    let needed = format.boxes_per_offer * format.cards_per_box;
+    let cards: Vec<CardType> =
+        if format.duplicates == DuplicatePolicy::UniqueInOffer && pool.len() >= needed {
+            pool.partial_shuffle(rng, needed).0.to_vec()
+        } else {
+            (0..needed)
+                .map(|_| *pool.choose(rng).expect("Pool is not empty"))
+                .collect()
+        };
+
+    Ok(cards
+        .chunks(format.cards_per_box.max(1))
+        .map(|chunk| chunk.to_vec())
+        .collect())
+}
+
+/// Loads all cards printed in `set_names`, optionally restricted to `archetypes`,
+/// sorted by id.
+fn load_singles(
+    connection: &Connection,
+    set_names: &[String],
+    archetypes: Option<&Vec<String>>,
+) -> rusqlite::Result<Vec<CardType>> {
+    let set_names_json = serde_json::to_string(set_names).expect("Strings are valid JSON");
+
+    match archetypes {
+        Some(archetypes) => connection
+            .prepare_cached(
+                "SELECT DISTINCT cards.id FROM cards
+                JOIN set_contents ON set_contents.card_id = cards.id
+                WHERE set_contents.set_name IN (SELECT value FROM json_each(?1))
+                AND cards.archetype IN (SELECT value FROM json_each(?2))
+                ORDER BY cards.id",
+            )?
+            .query_map(
+                (
+                    set_names_json,
+                    serde_json::to_string(archetypes).expect("Strings are valid JSON"),
+                ),
+                |row| row.get(0),
+            )?
+            .collect(),
+        None => connection
+            .prepare_cached(
+                "SELECT DISTINCT card_id FROM set_contents
+                WHERE set_name IN (SELECT value FROM json_each(?1))
+                ORDER BY card_id",
+            )?
+            .query_map([set_names_json], |row| row.get(0))?
+            .collect(),
+    }
+}
+
+/// Opens the `count` packs that `round` grants, cycling through `set_names`.
+pub fn open_round_packs(
+    connection: &Connection,
+    collection: &Collection,
+    round: u16,
+    set_names: &[String],
+    layout: &PackLayout,
+    count: usize,
+) -> Result<Vec<Vec<Card>>, Box<dyn Error>> {
+    let mut rng = round_rng(collection.seed, round, RngPurpose::PackOpening);
+    generate_packs(connection, set_names, layout, count, &mut rng)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations;
+    use crate::user_data::collection::{ChangeContent, MetaData};
+
+    const SET: &str = "Test Set";
+
+    /// Database with one set of 40 cards: 30 commons, 5 rares and 5 foils. Even cards
+    /// belong to the archetype "Even".
+    fn database() -> Connection {
+        let mut connection = Connection::open_in_memory().unwrap();
+        migrations::migrate(&mut connection).unwrap();
+
+        connection
+            .execute(
+                "INSERT INTO sets (name, date) VALUES (?1, '2002-03-08')",
+                [SET],
+            )
+            .unwrap();
+        for id in 1..=40 {
+            let rarity = match id {
+                1..=30 => "Common",
+                31..=35 => "Rare",
+                36..=38 => "Super Rare",
+                39 => "Ultra Rare",
+                _ => "Secret Rare",
+            };
+            let archetype = if id % 2 == 0 { "Even" } else { "Odd" };
+
+            connection
+                .execute(
+                    "INSERT INTO cards (id, name, archetype) VALUES (?1, ?2, ?3)",
+                    (id, format!("Card {}", id), archetype),
+                )
+                .unwrap();
+            connection
+                .execute(
+                    "INSERT INTO set_contents (card_id, set_name, rarity) VALUES (?1, ?2, ?3)",
+                    (id, SET, rarity),
+                )
+                .unwrap();
+        }
+
+        connection
+    }
+
+    fn collection(seed: u64, pool: PoolSource, duplicates: DuplicatePolicy) -> Collection {
+        let mut collection = Collection::new(MetaData::default(), seed);
+        collection.draft_format = DraftFormat {
+            boxes_per_offer: 3,
+            cards_per_box: 5,
+            picks_per_offer: 1,
+            offers_per_round: 3,
+            pool,
+            duplicates,
+        };
+
+        collection
+    }
+
+    fn offer(
+        connection: &Connection,
+        collection: &Collection,
+        round: u16,
+        offer: u16,
+    ) -> Vec<Vec<CardType>> {
+        let sets = [SET.to_string()];
+        generate_offer(connection, collection, round, offer, &sets, &sets).unwrap()
+    }
+
+    fn pools() -> [PoolSource; 3] {
+        [
+            PoolSource::Sets,
+            PoolSource::Archetypes(vec!["Even".to_string()]),
+            PoolSource::CardPool,
+        ]
+    }
+
+    #[test]
+    fn offers_are_deterministic() {
+        let connection = database();
+
+        for pool in pools() {
+            let collection = collection(7, pool, DuplicatePolicy::Allow);
+            let first = offer(&connection, &collection, 1, 0);
+
+            assert_eq!(first.len(), 3);
+            assert!(first.iter().all(|cards| cards.len() == 5));
+            assert_eq!(offer(&connection, &collection, 1, 0), first);
+            assert_eq!(offer(&database(), &collection, 1, 0), first);
+        }
+    }
+
+    #[test]
+    fn offers_depend_on_seed_round_and_offer() {
+        let connection = database();
+
+        for pool in pools() {
+            let collection = collection(7, pool.clone(), DuplicatePolicy::Allow);
+            let first = offer(&connection, &collection, 1, 0);
+
+            assert_ne!(offer(&connection, &collection, 1, 1), first);
+            assert_ne!(offer(&connection, &collection, 2, 0), first);
+            assert_ne!(
+                offer(
+                    &connection,
+                    &self::collection(8, pool, DuplicatePolicy::Allow),
+                    1,
+                    0
+                ),
+                first
+            );
+        }
+    }
+
+    #[test]
+    fn pack_openings_are_deterministic() {
+        let connection = database();
+        let sets = [SET.to_string()];
+        let open = |seed, round| {
+            let collection = collection(seed, PoolSource::Sets, DuplicatePolicy::Allow);
+            open_round_packs(
+                &connection,
+                &collection,
+                round,
+                &sets,
+                &PackLayout::standard(),
+                4,
+            )
+            .unwrap()
+        };
+
+        let packs = open(7, 1);
+        assert_eq!(packs.len(), 4);
+        assert!(packs.iter().all(|pack| pack.len() == 10));
+        assert_eq!(open(7, 1), packs);
+        assert_ne!(open(7, 2), packs);
+        assert_ne!(open(8, 1), packs);
+    }
+
+    #[test]
+    fn unique_in_offer() {
+        let connection = database();
+        let collection = collection(7, PoolSource::CardPool, DuplicatePolicy::UniqueInOffer);
+
+        let mut cards: Vec<CardType> = offer(&connection, &collection, 1, 0).concat();
+        cards.sort_unstable();
+        cards.dedup();
+
+        assert_eq!(cards.len(), 15);
+    }
+
+    #[test]
+    fn exclude_owned_ignores_picks_of_the_round() {
+        let connection = database();
+        let mut collection = collection(7, PoolSource::CardPool, DuplicatePolicy::ExcludeOwned);
+        let owned: Vec<Card> = (1..=20).map(|id| Card { id }).collect();
+        collection.add_change(Change::Add(ChangeContent::new(owned, String::new(), None)));
+
+        let offers: Vec<_> = (0..3)
+            .map(|index| offer(&connection, &collection, 1, index))
+            .collect();
+        assert!(offers.concat().concat().iter().all(|id| *id > 20));
+
+        // Picking from the first offer must not change the remaining ones.
+        collection.add_change(Change::Add(ChangeContent::new(
+            vec![Card {
+                id: offers[0][0][0],
+            }],
+            String::new(),
+            Some(1),
+        )));
+        collection.progression.completed_offers = 1;
+
+        for index in 1..3 {
+            assert_eq!(
+                offer(&connection, &collection, 1, index),
+                offers[index as usize]
+            );
+        }
+
+        // Cards picked in earlier rounds are owned by the next one.
+        assert!(offer(&connection, &collection, 2, 0)
+            .concat()
+            .iter()
+            .all(|id| *id != offers[0][0][0]));
+    }
+}
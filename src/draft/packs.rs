@@ -0,0 +1,321 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+
+use rand::distributions::WeightedIndex;
+use rand::prelude::*;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::user_data::collection::{Card, CardType};
+
+/// One or more cards in a pack that share the same rarity odds.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Slot {
+    pub count: usize,
+    /// Rarities (as stored in `set_contents.rarity`) and their relative weights.
+    pub rarities: Vec<(String, u32)>,
+}
+
+impl Slot {
+    pub fn new(count: usize, rarities: &[(&str, u32)]) -> Self {
+        Self {
+            count,
+            rarities: rarities
+                .iter()
+                .map(|(rarity, weight)| (rarity.to_string(), *weight))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PackLayout {
+    pub slots: Vec<Slot>,
+}
+
+impl PackLayout {
+    /// 8 commons, 1 rare and 1 foil with Super/Ultra/Secret Rare odds of 6:3:1.
+    pub fn standard() -> Self {
+        Self::sized(10)
+    }
+
+    /// Layout of `size` cards with one rare and one foil slot, all other cards being
+    /// commons. Packs of a single card only have the foil slot.
+    pub fn sized(size: usize) -> Self {
+        let foil = Slot::new(
+            1,
+            &[("Super Rare", 6), ("Ultra Rare", 3), ("Secret Rare", 1)],
+        );
+
+        let slots = match size {
+            0 => Vec::new(),
+            1 => vec![foil],
+            _ => vec![
+                Slot::new(size - 2, &[("Common", 1)]),
+                Slot::new(1, &[("Rare", 1)]),
+                foil,
+            ],
+        };
+
+        Self { slots }
+    }
+
+    pub fn size(&self) -> usize {
+        self.slots.iter().map(|slot| slot.count).sum()
+    }
+}
+
+impl Default for PackLayout {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+/// Cards of a set grouped by rarity.
+#[derive(Debug, Clone, Default)]
+pub struct SetPool {
+    pub set_name: String,
+    rarities: BTreeMap<String, Vec<CardType>>,
+}
+
+impl SetPool {
+    /// Loads the contents of the set called `set_name`. Results are sorted so the same
+    /// random number generator state always produces the same packs.
+    pub fn load(connection: &Connection, set_name: &str) -> rusqlite::Result<Self> {
+        let mut rarities: BTreeMap<String, Vec<CardType>> = BTreeMap::new();
+
+        let mut statement = connection.prepare_cached(
+            "SELECT DISTINCT rarity, card_id FROM set_contents
+            WHERE set_name = ?1 AND rarity IS NOT NULL
+            ORDER BY rarity, card_id",
+        )?;
+        let rows = statement.query_map([set_name], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        for row in rows {
+            let (rarity, card_id): (String, CardType) = row?;
+            rarities.entry(rarity).or_default().push(card_id);
+        }
+
+        Ok(Self {
+            set_name: set_name.to_string(),
+            rarities,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rarities.is_empty()
+    }
+
+    /// Returns a copy of the pool without the cards for which `exclude` returns `true`.
+    pub fn without(&self, exclude: impl Fn(CardType) -> bool) -> Self {
+        Self {
+            set_name: self.set_name.clone(),
+            rarities: self
+                .rarities
+                .iter()
+                .map(|(rarity, cards)| {
+                    (
+                        rarity.clone(),
+                        cards.iter().copied().filter(|id| !exclude(*id)).collect(),
+                    )
+                })
+                .filter(|(_, cards): &(String, Vec<CardType>)| !cards.is_empty())
+                .collect(),
+        }
+    }
+
+    fn all_cards(&self) -> Vec<CardType> {
+        let mut cards: Vec<CardType> = self.rarities.values().flatten().copied().collect();
+        cards.sort_unstable();
+        cards.dedup();
+        cards
+    }
+
+    /// Picks the cards for one slot. Rarities that are not in the set are ignored; if
+    /// none of the slot's rarities are, any card of the set can be picked.
+    fn fill_slot<R: Rng + ?Sized>(&self, slot: &Slot, pack: &mut Vec<Card>, rng: &mut R) {
+        let available: Vec<(&Vec<CardType>, u32)> = slot
+            .rarities
+            .iter()
+            .filter_map(|(rarity, weight)| {
+                self.rarities
+                    .get(rarity)
+                    .filter(|_| *weight > 0)
+                    .map(|cards| (cards, *weight))
+            })
+            .collect();
+        let fallback = self.all_cards();
+
+        for _ in 0..slot.count {
+            let cards = match WeightedIndex::new(available.iter().map(|(_, weight)| weight)) {
+                Ok(distribution) => available[distribution.sample(rng)].0,
+                Err(_) => &fallback,
+            };
+
+            // Avoid duplicates within a pack unless there is no other choice.
+            let unused: Vec<CardType> = cards
+                .iter()
+                .filter(|id| !pack.iter().any(|card| card.id == **id))
+                .copied()
+                .collect();
+            let candidates = if unused.is_empty() { cards } else { &unused };
+
+            if let Some(id) = candidates.choose(rng) {
+                pack.push(Card { id: *id });
+            }
+        }
+    }
+
+    pub fn open_pack<R: Rng + ?Sized>(&self, layout: &PackLayout, rng: &mut R) -> Vec<Card> {
+        let mut pack = Vec::with_capacity(layout.size());
+
+        for slot in layout.slots.iter() {
+            self.fill_slot(slot, &mut pack, rng);
+        }
+
+        pack
+    }
+}
+
+/// Opens `count` packs, cycling through the sets in `set_names`. Sets without cards
+/// are skipped.
+pub fn generate_packs<R: Rng + ?Sized>(
+    connection: &Connection,
+    set_names: &[String],
+    layout: &PackLayout,
+    count: usize,
+    rng: &mut R,
+) -> Result<Vec<Vec<Card>>, Box<dyn Error>> {
+    let pools = set_names
+        .iter()
+        .map(|set_name| SetPool::load(connection, set_name))
+        .collect::<rusqlite::Result<Vec<SetPool>>>()?;
+
+    if pools.iter().all(|pool| pool.is_empty()) {
+        return Err("None of the sets have cards to generate packs from.".into());
+    }
+
+    Ok(pools
+        .iter()
+        .filter(|pool| !pool.is_empty())
+        .cycle()
+        .take(count)
+        .map(|pool| pool.open_pack(layout, rng))
+        .collect())
+}
+
+/// Converts a pack into the card ids expected by `DraftContainer`.
+pub fn to_ids(pack: &[Card]) -> Vec<CardType> {
+    pack.iter().map(|card| card.id).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::testing::fixture_database;
+
+    const LOB: &str = "Legend of Blue Eyes White Dragon";
+    const MRD: &str = "Metal Raiders";
+
+    fn open(connection: &Connection, set_names: &[&str], layout: &PackLayout) -> Vec<Vec<Card>> {
+        let set_names: Vec<String> = set_names.iter().map(|name| name.to_string()).collect();
+        generate_packs(
+            connection,
+            &set_names,
+            layout,
+            3,
+            &mut StdRng::seed_from_u64(7),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn layout_sizes() {
+        assert_eq!(PackLayout::sized(0).slots, []);
+        assert_eq!(PackLayout::sized(1).slots.len(), 1);
+        assert_eq!(PackLayout::sized(1).size(), 1);
+        assert_eq!(
+            PackLayout::sized(5)
+                .slots
+                .iter()
+                .map(|slot| slot.count)
+                .collect::<Vec<_>>(),
+            [3, 1, 1]
+        );
+        assert_eq!(PackLayout::default().size(), 10);
+    }
+
+    #[test]
+    fn packs_follow_slots() {
+        let connection = fixture_database();
+        let layout = PackLayout {
+            slots: vec![
+                Slot::new(1, &[("Rare", 1)]),
+                Slot::new(2, &[("Ultra Rare", 1), ("Common", 0)]),
+                Slot::new(2, &[("Common", 1)]),
+            ],
+        };
+
+        for pack in open(&connection, &[LOB], &layout) {
+            let mut foils = to_ids(&pack[1..3]);
+            foils.sort_unstable();
+
+            assert_eq!(pack.len(), layout.size());
+            assert_eq!(pack[0].id, 55144522);
+            assert_eq!(foils, [46986414, 89631139]);
+            // The only common is repeated once no other card is left.
+            assert_eq!(to_ids(&pack[3..]), [15025844, 15025844]);
+        }
+    }
+
+    #[test]
+    fn missing_rarities() {
+        let connection = fixture_database();
+
+        // Metal Raiders has one common, no rares and no Ultra Rares.
+        for pack in open(&connection, &[MRD], &PackLayout::standard()) {
+            assert_eq!(pack.len(), 10);
+            assert!(to_ids(&pack[..8]).iter().all(|id| *id == 32864));
+            // The rare slot falls back to any card and prefers unused ones.
+            assert!([40640057, 25451652].contains(&pack[8].id));
+            assert!([40640057, 25451652].contains(&pack[9].id));
+        }
+
+        let layout = PackLayout {
+            slots: vec![Slot::new(4, &[("Ghost Rare", 1), ("Common", 0)])],
+        };
+        for pack in open(&connection, &[MRD], &layout) {
+            let mut cards = to_ids(&pack);
+            cards.sort_unstable();
+            cards.dedup();
+            // Any card of the set can be picked, but the first three are unique.
+            assert_eq!(cards, [32864, 25451652, 40640057]);
+        }
+    }
+
+    #[test]
+    fn cycles_through_sets() {
+        let connection = fixture_database();
+        let lob = SetPool::load(&connection, LOB).unwrap().all_cards();
+        let mrd = SetPool::load(&connection, MRD).unwrap().all_cards();
+
+        let packs = open(
+            &connection,
+            &[LOB, "Unknown Set", MRD],
+            &PackLayout::sized(3),
+        );
+        assert_eq!(packs.len(), 3);
+        for (pack, cards) in packs.iter().zip([&lob, &mrd, &lob]) {
+            assert!(to_ids(pack).iter().all(|id| cards.contains(id)));
+        }
+
+        assert!(generate_packs(
+            &connection,
+            &["Unknown Set".to_string()],
+            &PackLayout::standard(),
+            1,
+            &mut StdRng::seed_from_u64(7),
+        )
+        .is_err());
+    }
+}
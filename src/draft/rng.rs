@@ -0,0 +1,87 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+/// What a random number generator from [`round_rng`] is used for. Each purpose gets an
+/// independent stream, so e.g. opening packs never changes the draft offers of a round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RngPurpose {
+    PackOpening,
+    DraftOffer(u16),
+}
+
+/// Returns a random number generator that only depends on its arguments, so replaying
+/// a round of a collection with the same seed yields identical results.
+pub fn round_rng(seed: u64, round: u16, purpose: RngPurpose) -> ChaCha8Rng {
+    let stream = match purpose {
+        RngPurpose::PackOpening => 0,
+        RngPurpose::DraftOffer(offer) => 1 + offer as u64,
+    };
+
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(((round as u64) << 32) | stream);
+    rng
+}
+
+pub fn new_seed() -> u64 {
+    rand::random()
+}
+
+/// Derives a seed from `bytes` with 64 bit FNV-1a, which unlike the hashers of the
+/// standard library gives the same result on every platform and Rust version.
+pub fn seed_from_bytes(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+
+    fn sample(seed: u64, round: u16, purpose: RngPurpose) -> Vec<u64> {
+        let mut rng = round_rng(seed, round, purpose);
+        (0..8).map(|_| rng.gen()).collect()
+    }
+
+    #[test]
+    fn same_arguments_same_numbers() {
+        assert_eq!(
+            sample(7, 3, RngPurpose::DraftOffer(2)),
+            sample(7, 3, RngPurpose::DraftOffer(2))
+        );
+        assert_eq!(
+            sample(7, 3, RngPurpose::PackOpening),
+            sample(7, 3, RngPurpose::PackOpening)
+        );
+    }
+
+    #[test]
+    fn streams_are_independent() {
+        let mut samples = Vec::new();
+        for seed in [7, 8] {
+            for round in [1, 2, u16::MAX] {
+                for purpose in [
+                    RngPurpose::PackOpening,
+                    RngPurpose::DraftOffer(0),
+                    RngPurpose::DraftOffer(1),
+                    RngPurpose::DraftOffer(u16::MAX),
+                ] {
+                    samples.push(sample(seed, round, purpose));
+                }
+            }
+        }
+
+        for (index, sample) in samples.iter().enumerate() {
+            assert!(!samples[index + 1..].contains(sample));
+        }
+    }
+
+    #[test]
+    fn seeds_from_bytes_are_stable() {
+        assert_eq!(seed_from_bytes(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(seed_from_bytes(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(seed_from_bytes(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+}
@@ -0,0 +1,397 @@
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::data::dirs;
+use crate::user_data::collection::CardType;
+
+pub const DEFAULT_BASE_URL: &str = "https://images.ygoprodeck.com/images";
+
+/// Environment variable overriding [`DEFAULT_BASE_URL`].
+pub const BASE_URL_ENV: &str = "YGO_DESTINY_IMAGE_URL";
+/// Environment variable pointing to a local directory to copy images from instead of
+/// downloading them. It must have the same layout as the remote image server.
+pub const LOCAL_DIR_ENV: &str = "YGO_DESTINY_IMAGE_DIR";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageKind {
+    Full,
+    Small,
+    /// Artwork only.
+    Cropped,
+}
+
+impl ImageKind {
+    pub const ALL: [ImageKind; 3] = [ImageKind::Full, ImageKind::Small, ImageKind::Cropped];
+
+    /// Directory of this kind of image on the image server.
+    fn remote_dir(&self) -> &'static str {
+        match self {
+            ImageKind::Full => "cards",
+            ImageKind::Small => "cards_small",
+            ImageKind::Cropped => "cards_cropped",
+        }
+    }
+
+    /// Directory of this kind of image relative to the cache directory. Full images are
+    /// kept at the top level.
+    fn cache_dir(&self) -> Option<&'static str> {
+        match self {
+            ImageKind::Full => None,
+            ImageKind::Small => Some("small"),
+            ImageKind::Cropped => Some("cropped"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageSource {
+    /// Base URL of an image server, e.g. [`DEFAULT_BASE_URL`].
+    Remote(String),
+    Local(PathBuf),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Progress {
+    pub id: CardType,
+    pub done: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct FetchReport {
+    pub fetched: Vec<CardType>,
+    pub failed: Vec<(CardType, String)>,
+}
+
+/// Checks whether `bytes` is a complete JPEG file.
+pub fn verify(bytes: &[u8]) -> bool {
+    bytes.len() > 4 && bytes.starts_with(&[0xFF, 0xD8, 0xFF]) && bytes.ends_with(&[0xFF, 0xD9])
+}
+
+/// Path of a cached image in the default cache directory. The file may not exist.
+pub fn cached_path(id: CardType, kind: ImageKind) -> PathBuf {
+    path_in(dirs::IMAGES.to_path_buf(), id, kind)
+}
+
+fn dir_in(mut dir: PathBuf, kind: ImageKind) -> PathBuf {
+    if let Some(sub_dir) = kind.cache_dir() {
+        dir.push(sub_dir);
+    }
+    dir
+}
+
+fn path_in(dir: PathBuf, id: CardType, kind: ImageKind) -> PathBuf {
+    dir_in(dir, kind).join(format!("{}.jpg", id))
+}
+
+/// Fetches card images from an [`ImageSource`] into a cache directory.
+#[derive(Debug)]
+pub struct ImageManager {
+    source: ImageSource,
+    cache_dir: PathBuf,
+    client: reqwest::blocking::Client,
+}
+
+impl ImageManager {
+    pub fn new(source: ImageSource, cache_dir: PathBuf) -> Self {
+        Self {
+            source,
+            cache_dir,
+            client: reqwest::blocking::Client::new(),
+        }
+    }
+
+    /// Uses the source given by [`LOCAL_DIR_ENV`] or [`BASE_URL_ENV`] and caches into
+    /// [`dirs::IMAGES`].
+    pub fn from_env() -> Self {
+        let source = match (env::var_os(LOCAL_DIR_ENV), env::var(BASE_URL_ENV)) {
+            (Some(dir), _) => ImageSource::Local(PathBuf::from(dir)),
+            (None, Ok(url)) => ImageSource::Remote(url),
+            (None, Err(_)) => ImageSource::Remote(DEFAULT_BASE_URL.to_string()),
+        };
+
+        Self::new(source, dirs::IMAGES.to_path_buf())
+    }
+
+    pub fn path(&self, id: CardType, kind: ImageKind) -> PathBuf {
+        path_in(self.cache_dir.clone(), id, kind)
+    }
+
+    /// Checks whether a non-empty image is cached. Images are only cached after they
+    /// were verified, so the contents are not checked again; see [`Self::verify_cache`].
+    pub fn is_cached(&self, id: CardType, kind: ImageKind) -> bool {
+        fs::metadata(self.path(id, kind))
+            .is_ok_and(|metadata| metadata.is_file() && metadata.len() > 0)
+    }
+
+    /// Makes sure the image is cached and returns its path.
+    pub fn fetch(&self, id: CardType, kind: ImageKind) -> Result<PathBuf, Box<dyn Error>> {
+        if self.is_cached(id, kind) {
+            return Ok(self.path(id, kind));
+        }
+
+        self.download(id, kind)
+    }
+
+    /// Downloads the image into the cache, replacing any cached version.
+    fn download(&self, id: CardType, kind: ImageKind) -> Result<PathBuf, Box<dyn Error>> {
+        let path = self.path(id, kind);
+        let file_name = format!("{}/{}.jpg", kind.remote_dir(), id);
+        let bytes = match &self.source {
+            ImageSource::Remote(base_url) => self
+                .client
+                .get(format!("{}/{}", base_url.trim_end_matches('/'), file_name))
+                .send()?
+                .error_for_status()?
+                .bytes()?
+                .to_vec(),
+            ImageSource::Local(dir) => fs::read(dir.join(file_name))?,
+        };
+
+        if !verify(&bytes) {
+            return Err(format!("Received an invalid image for card {}.", id).into());
+        }
+
+        // Write to a temporary file first so an interrupted write never leaves a
+        // truncated image in the cache.
+        fs::create_dir_all(path.parent().expect("Image paths have a parent"))?;
+        let partial_path = path.with_extension("part");
+        fs::write(&partial_path, bytes)?;
+        fs::rename(&partial_path, &path)?;
+
+        Ok(path)
+    }
+
+    /// Fetches every image in `ids` that is not cached yet, calling `progress` after
+    /// each image. Failures are collected rather than aborting. Stops early once
+    /// `cancel` is set.
+    pub fn fetch_missing(
+        &self,
+        ids: &[CardType],
+        kind: ImageKind,
+        cancel: &AtomicBool,
+        mut progress: impl FnMut(Progress),
+    ) -> FetchReport {
+        let mut report = FetchReport::default();
+
+        for (index, id) in ids.iter().enumerate() {
+            if cancel.load(Ordering::Relaxed) {
+                break;
+            }
+
+            if !self.is_cached(*id, kind) {
+                match self.download(*id, kind) {
+                    Ok(_) => report.fetched.push(*id),
+                    Err(error) => report.failed.push((*id, error.to_string())),
+                }
+            }
+
+            progress(Progress {
+                id: *id,
+                done: index + 1,
+                total: ids.len(),
+            });
+        }
+
+        report
+    }
+
+    /// Removes invalid cached images of `kind` and returns their ids.
+    pub fn verify_cache(&self, kind: ImageKind) -> Result<Vec<CardType>, Box<dyn Error>> {
+        let dir = dir_in(self.cache_dir.clone(), kind);
+        let mut removed = Vec::new();
+
+        if !dir.is_dir() {
+            return Ok(removed);
+        }
+
+        for entry in dir.read_dir()? {
+            let path = entry?.path();
+
+            let Some(id) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse::<CardType>().ok())
+            else {
+                continue;
+            };
+
+            if path.extension().is_some_and(|extension| extension == "jpg")
+                && !verify(&fs::read(&path)?)
+            {
+                fs::remove_file(&path)?;
+                removed.push(id);
+            }
+        }
+
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::*;
+
+    const IMAGE: [u8; 6] = [0xFF, 0xD8, 0xFF, 0xE0, 0xFF, 0xD9];
+    const TRUNCATED: [u8; 5] = [0xFF, 0xD8, 0xFF, 0xE0, 0x00];
+
+    /// Local stand-in for the image server. Card 1 is valid, card 2 is truncated and
+    /// every other card is missing. Returns its base URL and the number of requests.
+    fn server() -> (String, Arc<AtomicUsize>) {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", server.server_addr().to_ip().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let counter = requests.clone();
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let response = match request.url() {
+                    "/cards/1.jpg" | "/cards_small/1.jpg" => {
+                        tiny_http::Response::from_data(IMAGE.to_vec())
+                    }
+                    "/cards/2.jpg" => tiny_http::Response::from_data(TRUNCATED.to_vec()),
+                    _ => tiny_http::Response::from_data(Vec::new()).with_status_code(404),
+                };
+                let _ = request.respond(response);
+            }
+        });
+
+        (url, requests)
+    }
+
+    #[test]
+    fn fetch_caches_images() {
+        let (url, requests) = server();
+        let cache = tempfile::tempdir().unwrap();
+        let manager = ImageManager::new(ImageSource::Remote(url), cache.path().to_path_buf());
+
+        let path = manager.fetch(1, ImageKind::Full).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), IMAGE);
+        assert!(manager.is_cached(1, ImageKind::Full));
+        assert!(!manager.is_cached(1, ImageKind::Small));
+
+        assert_eq!(manager.fetch(1, ImageKind::Full).unwrap(), path);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        let small = manager.fetch(1, ImageKind::Small).unwrap();
+        assert_eq!(small, manager.cache_dir.join("small").join("1.jpg"));
+    }
+
+    #[test]
+    fn invalid_images_are_not_cached() {
+        let (url, _) = server();
+        let cache = tempfile::tempdir().unwrap();
+        let manager = ImageManager::new(ImageSource::Remote(url), cache.path().to_path_buf());
+
+        assert!(manager.fetch(2, ImageKind::Full).is_err());
+        assert!(manager.fetch(3, ImageKind::Full).is_err());
+        assert!(!manager.path(2, ImageKind::Full).exists());
+        assert!(!manager
+            .path(2, ImageKind::Full)
+            .with_extension("part")
+            .exists());
+    }
+
+    #[test]
+    fn fetch_missing_reports_failures() {
+        let (url, requests) = server();
+        let cache = tempfile::tempdir().unwrap();
+        let manager = ImageManager::new(ImageSource::Remote(url), cache.path().to_path_buf());
+        manager.fetch(1, ImageKind::Full).unwrap();
+
+        let mut progress = Vec::new();
+        let report = manager.fetch_missing(
+            &[1, 2, 3],
+            ImageKind::Full,
+            &AtomicBool::new(false),
+            |image| progress.push((image.id, image.done, image.total)),
+        );
+
+        assert!(report.fetched.is_empty());
+        assert_eq!(
+            report.failed.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            [2, 3]
+        );
+        assert_eq!(progress, [(1, 1, 3), (2, 2, 3), (3, 3, 3)]);
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn fetch_missing_stops_when_cancelled() {
+        let (url, requests) = server();
+        let cache = tempfile::tempdir().unwrap();
+        let manager = ImageManager::new(ImageSource::Remote(url), cache.path().to_path_buf());
+
+        let report =
+            manager.fetch_missing(&[1, 2], ImageKind::Full, &AtomicBool::new(true), |_| {
+                panic!("No image should be fetched.")
+            });
+
+        assert!(report.fetched.is_empty() && report.failed.is_empty());
+        assert_eq!(requests.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn verify_cache_removes_invalid_images() {
+        let cache = tempfile::tempdir().unwrap();
+        let manager = ImageManager::new(
+            ImageSource::Remote(String::new()),
+            cache.path().join("images"),
+        );
+        assert!(manager.verify_cache(ImageKind::Full).unwrap().is_empty());
+
+        fs::create_dir_all(&manager.cache_dir).unwrap();
+        fs::write(manager.path(1, ImageKind::Full), IMAGE).unwrap();
+        fs::write(manager.path(2, ImageKind::Full), TRUNCATED).unwrap();
+        fs::write(manager.cache_dir.join("notes.txt"), TRUNCATED).unwrap();
+        assert!(manager.is_cached(2, ImageKind::Full));
+
+        assert_eq!(manager.verify_cache(ImageKind::Full).unwrap(), [2]);
+        assert!(manager.is_cached(1, ImageKind::Full));
+        assert!(!manager.path(2, ImageKind::Full).exists());
+        assert!(manager.cache_dir.join("notes.txt").exists());
+    }
+
+    #[test]
+    fn empty_images_are_not_cached() {
+        let cache = tempfile::tempdir().unwrap();
+        let manager = ImageManager::new(
+            ImageSource::Remote(String::new()),
+            cache.path().to_path_buf(),
+        );
+
+        fs::write(manager.path(1, ImageKind::Full), []).unwrap();
+        fs::create_dir(manager.path(2, ImageKind::Full)).unwrap();
+
+        assert!(!manager.is_cached(1, ImageKind::Full));
+        assert!(!manager.is_cached(2, ImageKind::Full));
+        assert!(!manager.is_cached(3, ImageKind::Full));
+    }
+
+    #[test]
+    fn local_source() {
+        let source = tempfile::tempdir().unwrap();
+        fs::create_dir_all(source.path().join("cards")).unwrap();
+        fs::write(source.path().join("cards").join("1.jpg"), IMAGE).unwrap();
+        let cache = tempfile::tempdir().unwrap();
+        let manager = ImageManager::new(
+            ImageSource::Local(source.path().to_path_buf()),
+            cache.path().to_path_buf(),
+        );
+
+        let report =
+            manager.fetch_missing(&[1, 2], ImageKind::Full, &AtomicBool::new(false), |_| ());
+
+        assert_eq!(report.fetched, [1]);
+        assert_eq!(report.failed.len(), 1);
+        assert!(manager.is_cached(1, ImageKind::Full));
+    }
+}
@@ -0,0 +1,455 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use rusqlite::Connection;
+
+use crate::db::banlist::LimitStatus;
+use crate::db::cards::{self, CardInfo};
+use crate::user_data::collection::{Card, CardType, Deck, DeckSection};
+
+pub const MAIN_DECK_SIZE: RangeInclusive<usize> = 40..=60;
+pub const MAX_EXTRA_DECK_SIZE: usize = 15;
+pub const MAX_SIDE_DECK_SIZE: usize = 15;
+pub const MAX_COPIES: usize = 3;
+
+/// A reason a deck may not be played.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    MainDeckSize(usize),
+    ExtraDeckSize(usize),
+    SideDeckSize(usize),
+    /// More than [`MAX_COPIES`] copies across main, extra and side deck.
+    TooManyCopies {
+        card: CardType,
+        copies: usize,
+    },
+    /// More copies than the banlist allows.
+    Banlist {
+        card: CardType,
+        status: LimitStatus,
+        copies: usize,
+    },
+    /// A Fusion, Synchro, Xyz or Link monster in the main deck. They may still be
+    /// sided.
+    ExtraDeckCardInMain(CardType),
+    /// A card in the extra deck that does not belong there.
+    NotAnExtraDeckCard(CardType),
+    /// More copies than the collection owns.
+    NotOwned {
+        card: CardType,
+        copies: usize,
+        owned: usize,
+    },
+    /// The card is not in the `cards` table.
+    UnknownCard(CardType),
+}
+
+impl Violation {
+    /// The card the violation is about, if it is not about the deck as a whole.
+    pub fn card(&self) -> Option<CardType> {
+        match self {
+            Self::MainDeckSize(_) | Self::ExtraDeckSize(_) | Self::SideDeckSize(_) => None,
+            Self::TooManyCopies { card, .. }
+            | Self::Banlist { card, .. }
+            | Self::ExtraDeckCardInMain(card)
+            | Self::NotAnExtraDeckCard(card)
+            | Self::NotOwned { card, .. }
+            | Self::UnknownCard(card) => Some(*card),
+        }
+    }
+}
+
+/// Describes the violation without naming the card, see [`Violation::card`].
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MainDeckSize(size) => write!(
+                f,
+                "The main deck has {size} cards, but needs {} to {}.",
+                MAIN_DECK_SIZE.start(),
+                MAIN_DECK_SIZE.end()
+            ),
+            Self::ExtraDeckSize(size) => write!(
+                f,
+                "The extra deck has {size} cards, but may have at most {MAX_EXTRA_DECK_SIZE}."
+            ),
+            Self::SideDeckSize(size) => write!(
+                f,
+                "The side deck has {size} cards, but may have at most {MAX_SIDE_DECK_SIZE}."
+            ),
+            Self::TooManyCopies { copies, .. } => {
+                write!(f, "{copies} copies, but at most {MAX_COPIES} are allowed.")
+            }
+            Self::Banlist { status, copies, .. } => write!(
+                f,
+                "{copies} copies, but the card is {} ({} allowed).",
+                status.as_str(),
+                status.max_copies()
+            ),
+            Self::ExtraDeckCardInMain(_) => {
+                write!(f, "Belongs in the extra deck, not the main deck.")
+            }
+            Self::NotAnExtraDeckCard(_) => write!(f, "Does not belong in the extra deck."),
+            Self::NotOwned { copies, owned, .. } => {
+                write!(f, "{copies} copies, but the collection has {owned}.")
+            }
+            Self::UnknownCard(_) => write!(f, "Unknown card."),
+        }
+    }
+}
+
+/// Checks `deck` against the deck size limits, `banlist` and optionally the cards
+/// `owned` by a collection. `infos` has to contain every card of the deck; missing
+/// cards are reported as [`Violation::UnknownCard`].
+pub fn validate_with_infos(
+    deck: &Deck,
+    infos: &HashMap<CardType, CardInfo>,
+    banlist: &HashMap<CardType, LimitStatus>,
+    owned: Option<&HashMap<Card, u8>>,
+) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    if !MAIN_DECK_SIZE.contains(&deck.main.len()) {
+        violations.push(Violation::MainDeckSize(deck.main.len()));
+    }
+    if deck.extra.len() > MAX_EXTRA_DECK_SIZE {
+        violations.push(Violation::ExtraDeckSize(deck.extra.len()));
+    }
+    if deck.side.len() > MAX_SIDE_DECK_SIZE {
+        violations.push(Violation::SideDeckSize(deck.side.len()));
+    }
+
+    // Sorted so violations are reported in a stable order.
+    let mut copies: BTreeMap<CardType, usize> = BTreeMap::new();
+    for section in DeckSection::ALL {
+        for card in deck.section(section) {
+            *copies.entry(*card).or_default() += 1;
+        }
+    }
+
+    for (card, copies) in copies {
+        let Some(info) = infos.get(&card) else {
+            violations.push(Violation::UnknownCard(card));
+            continue;
+        };
+
+        if copies > MAX_COPIES {
+            violations.push(Violation::TooManyCopies { card, copies });
+        }
+
+        if let Some(status) = banlist.get(&card) {
+            if copies > status.max_copies() as usize {
+                violations.push(Violation::Banlist {
+                    card,
+                    status: *status,
+                    copies,
+                });
+            }
+        }
+
+        if let Some(owned) = owned {
+            let owned = owned.get(&Card { id: card }).copied().unwrap_or(0) as usize;
+            if copies > owned {
+                violations.push(Violation::NotOwned {
+                    card,
+                    copies,
+                    owned,
+                });
+            }
+        }
+
+        if info.is_extra_deck() && deck.main.contains(&card) {
+            violations.push(Violation::ExtraDeckCardInMain(card));
+        }
+        if !info.is_extra_deck() && deck.extra.contains(&card) {
+            violations.push(Violation::NotAnExtraDeckCard(card));
+        }
+    }
+
+    violations
+}
+
+/// Like [`validate_with_infos`], looking up the cards of the deck.
+pub fn validate(
+    connection: &Connection,
+    deck: &Deck,
+    banlist: &HashMap<CardType, LimitStatus>,
+    owned: Option<&HashMap<Card, u8>>,
+) -> rusqlite::Result<Vec<Violation>> {
+    let ids: Vec<CardType> = DeckSection::ALL
+        .iter()
+        .flat_map(|section| deck.section(*section).iter().copied())
+        .collect();
+    let infos = cards::get_many(connection, &ids)?;
+
+    Ok(validate_with_infos(deck, &infos, banlist, owned))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::banlist::{get_banlist, Format};
+    use crate::db::testing::fixture_database;
+
+    /// Extra deck monsters are 101 to 110, cycling through these types.
+    const EXTRA_TYPES: [&str; 4] = [
+        "Fusion Monster",
+        "Synchro Pendulum Effect Monster",
+        "XYZ Monster",
+        "Link Monster",
+    ];
+    const LINK: CardType = 104;
+    const EFFECT: CardType = 200;
+    const UNKNOWN: CardType = 999;
+
+    fn info(id: CardType, card_type: &str) -> CardInfo {
+        CardInfo {
+            id,
+            name: format!("Card {}", id),
+            card_type: card_type.to_string(),
+            description: String::new(),
+            atk: None,
+            def: None,
+            level: None,
+            race: None,
+            attribute: None,
+            archetype: None,
+            pend_scale: None,
+            link_rating: None,
+        }
+    }
+
+    fn infos() -> HashMap<CardType, CardInfo> {
+        (1..100)
+            .map(|id| info(id, "Normal Monster"))
+            .chain((101..=110).map(|id| info(id, EXTRA_TYPES[(id as usize - 101) % 4])))
+            .chain([info(EFFECT, "Effect Monster")])
+            .map(|info| (info.id, info))
+            .collect()
+    }
+
+    /// `size` cards with three copies each, starting at `first`.
+    fn cards(first: CardType, size: usize) -> Vec<CardType> {
+        (0..size)
+            .map(|index| first + index as CardType / 3)
+            .collect()
+    }
+
+    /// 40 main, 11 extra and 15 side deck cards. The side deck holds the third copy of
+    /// [`LINK`].
+    fn legal_deck() -> Deck {
+        Deck {
+            name: "Legal".to_string(),
+            main: cards(1, 40),
+            extra: cards(101, 11),
+            side: [cards(50, 14), vec![LINK]].concat(),
+        }
+    }
+
+    fn check(deck: &Deck) -> Vec<Violation> {
+        validate_with_infos(deck, &infos(), &HashMap::new(), None)
+    }
+
+    fn owned(deck: &Deck) -> HashMap<Card, u8> {
+        let mut owned = HashMap::new();
+        for section in DeckSection::ALL {
+            for card in deck.section(section) {
+                *owned.entry(Card { id: *card }).or_default() += 1;
+            }
+        }
+        owned
+    }
+
+    #[test]
+    fn legal_deck_has_no_violations() {
+        let deck = legal_deck();
+        let banlist = HashMap::from([
+            (1, LimitStatus::Unlimited),
+            (14, LimitStatus::Limited),
+            (99, LimitStatus::Forbidden),
+        ]);
+
+        assert_eq!(
+            validate_with_infos(&deck, &infos(), &banlist, Some(&owned(&deck))),
+            []
+        );
+    }
+
+    #[test]
+    fn main_deck_size() {
+        for (size, violations) in [
+            (39, vec![Violation::MainDeckSize(39)]),
+            (40, vec![]),
+            (60, vec![]),
+            (61, vec![Violation::MainDeckSize(61)]),
+        ] {
+            let deck = Deck {
+                main: cards(1, size),
+                ..legal_deck()
+            };
+            assert_eq!(check(&deck), violations, "{size} cards");
+        }
+    }
+
+    #[test]
+    fn extra_and_side_deck_size() {
+        for (size, violations) in [(15, vec![]), (16, vec![Violation::ExtraDeckSize(16)])] {
+            let deck = Deck {
+                extra: cards(101, size),
+                side: cards(50, 15),
+                ..legal_deck()
+            };
+            assert_eq!(check(&deck), violations, "{size} cards");
+        }
+
+        for (size, violations) in [(15, vec![]), (16, vec![Violation::SideDeckSize(16)])] {
+            let deck = Deck {
+                side: cards(50, size),
+                ..legal_deck()
+            };
+            assert_eq!(check(&deck), violations, "{size} cards");
+        }
+    }
+
+    #[test]
+    fn copies_count_across_sections() {
+        let mut deck = legal_deck();
+        deck.side[0] = 1;
+        deck.extra.push(LINK);
+
+        assert_eq!(
+            check(&deck),
+            [
+                Violation::TooManyCopies { card: 1, copies: 4 },
+                Violation::TooManyCopies {
+                    card: LINK,
+                    copies: 4
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn banlist() {
+        let banlist = HashMap::from([
+            (1, LimitStatus::Forbidden),
+            (2, LimitStatus::Limited),
+            (3, LimitStatus::SemiLimited),
+            (14, LimitStatus::Limited),
+            (50, LimitStatus::Forbidden),
+        ]);
+
+        assert_eq!(
+            validate_with_infos(&legal_deck(), &infos(), &banlist, None),
+            [
+                (1, LimitStatus::Forbidden),
+                (2, LimitStatus::Limited),
+                (3, LimitStatus::SemiLimited),
+                (50, LimitStatus::Forbidden)
+            ]
+            .map(|(card, status)| Violation::Banlist {
+                card,
+                status,
+                copies: 3
+            })
+        );
+    }
+
+    #[test]
+    fn extra_deck_cards_in_main_deck() {
+        // 105 to 108 are one monster of each extra deck type.
+        let deck = Deck {
+            main: [cards(1, 36), (105..=108).collect()].concat(),
+            ..legal_deck()
+        };
+        assert_eq!(
+            check(&deck),
+            (105..=108)
+                .map(Violation::ExtraDeckCardInMain)
+                .collect::<Vec<_>>()
+        );
+
+        let deck = Deck {
+            side: [cards(50, 10), (105..=108).collect(), vec![LINK]].concat(),
+            ..legal_deck()
+        };
+        assert!(check(&deck).is_empty());
+    }
+
+    #[test]
+    fn main_deck_cards_in_extra_deck() {
+        let mut deck = legal_deck();
+        deck.extra[0] = 60;
+        deck.extra[1] = EFFECT;
+
+        assert_eq!(
+            check(&deck),
+            [
+                Violation::NotAnExtraDeckCard(60),
+                Violation::NotAnExtraDeckCard(EFFECT),
+            ]
+        );
+    }
+
+    #[test]
+    fn not_owned() {
+        let deck = legal_deck();
+        let mut owned = owned(&deck);
+        owned.insert(Card { id: 1 }, 1);
+        owned.remove(&Card { id: LINK });
+
+        assert_eq!(
+            validate_with_infos(&deck, &infos(), &HashMap::new(), Some(&owned)),
+            [
+                Violation::NotOwned {
+                    card: 1,
+                    copies: 3,
+                    owned: 1
+                },
+                Violation::NotOwned {
+                    card: LINK,
+                    copies: 3,
+                    owned: 0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_cards() {
+        let mut deck = legal_deck();
+        deck.main[0] = UNKNOWN;
+        deck.extra[0] = UNKNOWN;
+
+        assert_eq!(check(&deck), [Violation::UnknownCard(UNKNOWN)]);
+    }
+
+    #[test]
+    fn validate_looks_up_cards() {
+        let connection = fixture_database();
+        let deck = Deck {
+            name: "Fixture".to_string(),
+            // Decode Talker, Pot of Greed and Blue-Eyes White Dragon.
+            main: vec![1861629, 55144522],
+            extra: vec![89631139],
+            side: vec![UNKNOWN],
+        };
+        let banlist = get_banlist(&connection, Format::Tcg).unwrap();
+
+        assert_eq!(
+            validate(&connection, &deck, &banlist, None).unwrap(),
+            [
+                Violation::MainDeckSize(2),
+                Violation::UnknownCard(UNKNOWN),
+                Violation::ExtraDeckCardInMain(1861629),
+                Violation::Banlist {
+                    card: 55144522,
+                    status: LimitStatus::Forbidden,
+                    copies: 1
+                },
+                Violation::NotAnExtraDeckCard(89631139),
+            ]
+        );
+    }
+}
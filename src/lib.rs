@@ -1,4 +1,9 @@
 pub mod data;
 pub mod db;
+pub mod deck_formats;
+pub mod draft;
+pub mod images;
+pub mod legality;
+pub mod progression;
 pub mod ui;
 pub mod user_data;
@@ -1,7 +1,5 @@
 use adw::prelude::ApplicationExt;
-use once_cell::sync::Lazy;
 use relm4::prelude::*;
-use rusqlite::Connection;
 
 use ygo_destiny::{
     data::{app_id, dirs, files, get_or_log},
@@ -12,27 +10,27 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::init();
     dirs::init()?;
 
-    let db_exists = files::DB.is_file();
-    let conn = Lazy::new(|| Connection::open(files::DB.as_path()).unwrap());
+    if !files::DB.is_file() && files::DB_BACKUP.is_file() {
+        get_or_log(db::restore_backup(), ());
+    }
+
+    let mut conn = db::migrations::open(files::DB.as_path())?;
 
-    if !db_exists {
-        match files::DB_BACKUP.is_file() {
-            true => get_or_log(db::restore_backup(), ()),
-            false => get_or_log(db::update(&conn), ()),
-        }
+    if !db::tables_intact(&conn) {
+        get_or_log(db::update(&mut conn, db::source::from_env().as_ref()), ());
     }
 
+    get_or_log(
+        db::banlist::import_snapshots_from_dir(&mut conn, dirs::BANLISTS.as_path()),
+        0,
+    );
+
     let main_app = relm4::main_application();
     main_app.set_application_id(Some(app_id::DOT_SEPARATED.as_str()));
     main_app.set_resource_base_path(Some(app_id::SLASH_SEPARATED.as_str()));
 
     let relm_app = RelmApp::from_app(main_app);
-    relm_app.run::<ui::App>(
-        match Lazy::into_value(conn) {
-            Ok(val) => val,
-            Err(func) => func(),
-        }
-    );
+    relm_app.run::<ui::App>(conn);
 
     Ok(())
 }
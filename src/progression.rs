@@ -0,0 +1,356 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use chrono::prelude::*;
+use rusqlite::Connection;
+
+use crate::db::banlist::{self, Format, LimitStatus};
+use crate::db::sets::{self, SetInfo};
+use crate::draft::{generate_offer, open_round_packs};
+use crate::user_data::collection::{
+    CardType, Change, ChangeContent, Collection, DraftFormat, Progression, LAST_CHANGED_FORMAT,
+};
+
+/// What a round gives to the collection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoundGrant {
+    pub packs: u16,
+    pub draft_offers: u16,
+}
+
+/// Determines the sets, packs and draft offers of each round of a collection's
+/// [`Progression`].
+pub struct ProgressionEngine<'a> {
+    connection: &'a Connection,
+    progression: Progression,
+    draft_format: DraftFormat,
+    /// All sets of the series in release order, starting with the first one.
+    sets: Vec<SetInfo>,
+}
+
+impl<'a> ProgressionEngine<'a> {
+    pub fn new(connection: &'a Connection, collection: &Collection) -> rusqlite::Result<Self> {
+        let progression = &collection.progression;
+        let mut sets: Vec<SetInfo> = sets::get_all(connection)?
+            .into_iter()
+            .filter(|set| set.date.is_some())
+            .collect();
+
+        if let Some(start_set) = &progression.start_set {
+            if let Some(start) = sets.iter().position(|set| &set.name == start_set) {
+                sets.drain(..start);
+            } else {
+                log::warn!(
+                    "Start set `{}` not found, starting with the oldest set.",
+                    start_set
+                );
+            }
+        }
+
+        Ok(Self {
+            connection,
+            progression: progression.clone(),
+            draft_format: collection.draft_format.clone(),
+            sets,
+        })
+    }
+
+    pub fn current_round(&self) -> u16 {
+        self.progression.current_round()
+    }
+
+    fn released_by(&self, round: u16) -> usize {
+        (round as usize * self.progression.sets_per_round as usize).min(self.sets.len())
+    }
+
+    /// Sets that are available in `round`, oldest first.
+    pub fn available_sets(&self, round: u16) -> &[SetInfo] {
+        &self.sets[..self.released_by(round)]
+    }
+
+    /// Sets that become available in `round`. Once every set has been released, the
+    /// newest `sets_per_round` sets stay in rotation.
+    pub fn new_sets(&self, round: u16) -> &[SetInfo] {
+        let end = self.released_by(round);
+        let mut start = self.released_by(round.saturating_sub(1));
+        if start == end {
+            start = end.saturating_sub(self.progression.sets_per_round as usize);
+        }
+
+        &self.sets[start..end]
+    }
+
+    /// Whether every set of the series was already released before `round`.
+    pub fn is_finished(&self, round: u16) -> bool {
+        self.released_by(round.saturating_sub(1)) == self.sets.len()
+    }
+
+    /// What completing a round gives. Currently the same for every round.
+    pub fn grant(&self) -> RoundGrant {
+        RoundGrant {
+            packs: self.progression.packs_per_round,
+            draft_offers: self.draft_format.offers_per_round,
+        }
+    }
+
+    /// Release date of the newest set available in `round`.
+    pub fn date(&self, round: u16) -> Option<&str> {
+        self.available_sets(round)
+            .last()
+            .and_then(|set| set.date.as_deref())
+    }
+
+    /// Limits of the banlist that was active when the newest set of `round` came out.
+    pub fn effective_banlist(
+        &self,
+        round: u16,
+        format: Format,
+    ) -> rusqlite::Result<Option<HashMap<CardType, LimitStatus>>> {
+        match self.date(round) {
+            Some(date) => banlist::limits_on(self.connection, date, format),
+            None => Ok(None),
+        }
+    }
+
+    fn new_set_names(&self, round: u16) -> Vec<String> {
+        self.new_sets(round)
+            .iter()
+            .map(|set| set.name.clone())
+            .collect()
+    }
+
+    fn available_set_names(&self, round: u16) -> Vec<String> {
+        self.available_sets(round)
+            .iter()
+            .map(|set| set.name.clone())
+            .collect()
+    }
+
+    /// Generates draft offer number `offer` of `round` according to the collection's
+    /// [`DraftFormat`].
+    pub fn offer(
+        &self,
+        collection: &Collection,
+        round: u16,
+        offer: u16,
+    ) -> Result<Vec<Vec<CardType>>, Box<dyn Error>> {
+        generate_offer(
+            self.connection,
+            collection,
+            round,
+            offer,
+            &self.new_set_names(round),
+            &self.available_set_names(round),
+        )
+    }
+
+    /// Generates the offers of the current round that have not been picked from yet.
+    pub fn remaining_offers(
+        &self,
+        collection: &Collection,
+    ) -> Result<Vec<Vec<Vec<CardType>>>, Box<dyn Error>> {
+        let round = self.current_round();
+
+        (collection.progression.completed_offers..self.grant().draft_offers)
+            .map(|offer| self.offer(collection, round, offer))
+            .collect()
+    }
+
+    /// Changes that completing the current round applies to `collection`.
+    pub fn round_changes(&self, collection: &Collection) -> Result<Vec<Change>, Box<dyn Error>> {
+        let round = self.current_round();
+        let grant = self.grant();
+
+        if grant.packs == 0 {
+            return Ok(Vec::new());
+        }
+
+        let cards = open_round_packs(
+            self.connection,
+            collection,
+            round,
+            &self.new_set_names(round),
+            &self.progression.pack_layout,
+            grant.packs as usize,
+        )?
+        .into_iter()
+        .flatten()
+        .collect();
+
+        Ok(vec![Change::Add(ChangeContent::new(
+            cards,
+            format!("{}", Utc::now().format(LAST_CHANGED_FORMAT)),
+            Some(round),
+        ))])
+    }
+
+    /// Applies the changes of the current round to `collection` and advances it to the
+    /// next round. Fails if not every draft offer of the round was picked from.
+    pub fn complete_round(&mut self, collection: &mut Collection) -> Result<(), Box<dyn Error>> {
+        let round = self.current_round();
+        let draft_offers = self.grant().draft_offers;
+        if collection.progression.completed_offers < draft_offers {
+            return Err(format!(
+                "Round {} has {} draft offers, but only {} were picked.",
+                round, draft_offers, collection.progression.completed_offers
+            )
+            .into());
+        }
+
+        for change in self.round_changes(collection)? {
+            collection.add_change(change);
+        }
+
+        collection.progression.completed_rounds += 1;
+        collection.progression.completed_offers = 0;
+        self.progression = collection.progression.clone();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::testing::fixture_database;
+    use crate::draft::PackLayout;
+    use crate::user_data::collection::MetaData;
+
+    const LOB: &str = "Legend of Blue Eyes White Dragon";
+    const SDY: &str = "Starter Deck: Yugi";
+    const MRD: &str = "Metal Raiders";
+
+    fn collection(start_set: Option<&str>, sets_per_round: u16) -> Collection {
+        let mut collection = Collection::new(MetaData::default(), 7);
+        collection.progression.start_set = start_set.map(str::to_string);
+        collection.progression.sets_per_round = sets_per_round;
+        collection.progression.packs_per_round = 2;
+        collection.progression.pack_layout = PackLayout::sized(5);
+
+        collection
+    }
+
+    fn names(sets: &[SetInfo]) -> Vec<&str> {
+        sets.iter().map(|set| set.name.as_str()).collect()
+    }
+
+    #[test]
+    fn sets_by_release_date() {
+        let connection = fixture_database();
+        connection
+            .execute("INSERT INTO sets (name) VALUES ('Undated')", ())
+            .unwrap();
+        let engine = ProgressionEngine::new(&connection, &collection(None, 1)).unwrap();
+
+        assert!(engine.available_sets(0).is_empty());
+        assert_eq!(names(engine.available_sets(1)), [LOB]);
+        assert_eq!(names(engine.available_sets(2)), [LOB, SDY]);
+        assert_eq!(names(engine.available_sets(4)), [LOB, SDY, MRD]);
+
+        assert_eq!(names(engine.new_sets(2)), [SDY]);
+        assert_eq!(names(engine.new_sets(3)), [MRD]);
+        assert_eq!(names(engine.new_sets(5)), [MRD]);
+
+        assert!(!engine.is_finished(3));
+        assert!(engine.is_finished(4));
+        assert_eq!(engine.date(0), None);
+        assert_eq!(engine.date(2), Some("2002-03-29"));
+    }
+
+    #[test]
+    fn start_set_and_sets_per_round() {
+        let connection = fixture_database();
+
+        let engine = ProgressionEngine::new(&connection, &collection(None, 2)).unwrap();
+        assert_eq!(names(engine.new_sets(1)), [LOB, SDY]);
+        assert_eq!(names(engine.new_sets(2)), [MRD]);
+        assert_eq!(names(engine.new_sets(3)), [SDY, MRD]);
+        assert!(!engine.is_finished(2));
+        assert!(engine.is_finished(3));
+
+        let engine = ProgressionEngine::new(&connection, &collection(Some(SDY), 2)).unwrap();
+        assert_eq!(names(engine.available_sets(1)), [SDY, MRD]);
+        assert_eq!(names(engine.new_sets(1)), [SDY, MRD]);
+        assert!(engine.is_finished(2));
+
+        let engine = ProgressionEngine::new(&connection, &collection(Some("Unknown"), 1)).unwrap();
+        assert_eq!(names(engine.new_sets(1)), [LOB]);
+    }
+
+    #[test]
+    fn round_changes() {
+        let connection = fixture_database();
+        let mut collection = collection(Some(MRD), 1);
+        let engine = ProgressionEngine::new(&connection, &collection).unwrap();
+        let mrd = sets::get_checklist(&connection, MRD).unwrap();
+
+        assert_eq!(
+            engine.grant(),
+            RoundGrant {
+                packs: 2,
+                draft_offers: 3
+            }
+        );
+
+        let changes = engine.round_changes(&collection).unwrap();
+        let [Change::Add(content)] = changes.as_slice() else {
+            panic!("Expected a single change, got {:?}", changes);
+        };
+        assert_eq!(content.round, Some(1));
+        assert_eq!(content.cards.len(), 10);
+        assert!(content
+            .cards
+            .iter()
+            .all(|card| mrd.iter().any(|printing| printing.card_id == card.id)));
+
+        let Change::Add(again) = &engine.round_changes(&collection).unwrap()[0] else {
+            unreachable!()
+        };
+        assert_eq!(again.cards, content.cards);
+
+        collection.progression.packs_per_round = 0;
+        let engine = ProgressionEngine::new(&connection, &collection).unwrap();
+        assert!(engine.round_changes(&collection).unwrap().is_empty());
+    }
+
+    #[test]
+    fn complete_round() {
+        let connection = fixture_database();
+        let mut collection = collection(None, 1);
+        let mut engine = ProgressionEngine::new(&connection, &collection).unwrap();
+
+        collection.progression.completed_offers = 2;
+        assert_eq!(engine.remaining_offers(&collection).unwrap().len(), 1);
+        assert!(engine.complete_round(&mut collection).is_err());
+        assert!(collection.changes.is_empty());
+        assert_eq!(collection.progression.completed_rounds, 0);
+
+        collection.progression.completed_offers = 3;
+        engine.complete_round(&mut collection).unwrap();
+        assert_eq!(collection.changes.len(), 1);
+        assert_eq!(
+            collection
+                .cards
+                .values()
+                .map(|count| *count as usize)
+                .sum::<usize>(),
+            10
+        );
+        assert_eq!(collection.progression.completed_rounds, 1);
+        assert_eq!(collection.progression.completed_offers, 0);
+        assert_eq!(engine.current_round(), 2);
+
+        // Offers picked before the format was changed to fewer offers still count.
+        collection.draft_format.offers_per_round = 1;
+        collection.progression.completed_offers = 3;
+        let mut engine = ProgressionEngine::new(&connection, &collection).unwrap();
+        assert!(engine.remaining_offers(&collection).unwrap().is_empty());
+        engine.complete_round(&mut collection).unwrap();
+        assert_eq!(engine.current_round(), 3);
+
+        collection.draft_format.offers_per_round = 0;
+        let mut engine = ProgressionEngine::new(&connection, &collection).unwrap();
+        engine.complete_round(&mut collection).unwrap();
+        assert_eq!(collection.changes.len(), 3);
+    }
+}
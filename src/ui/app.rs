@@ -1,4 +1,10 @@
 use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{self, AtomicBool};
+use std::sync::Arc;
 
 use adw::{gtk::Align, prelude::*};
 use chrono::prelude::*;
@@ -7,13 +13,33 @@ use once_cell::sync::OnceCell;
 use relm4::{factory::FactoryVecDeque, prelude::*};
 use relm4_icons::{icon_name, initialize_icons};
 
-use crate::data::get_or_log;
-use crate::db;
+use crate::data::{files, get_or_log};
+use crate::db::{
+    self,
+    banlist::{Format, LimitStatus},
+    search::CardQuery,
+    UpdateStage,
+};
+use crate::deck_formats::{import_ydk, ydke_to_cards, DeckImport};
+use crate::draft::new_seed;
+use crate::images::{ImageKind, ImageManager};
+use crate::progression::ProgressionEngine;
 use crate::ui::{
-    components::{CollectionData, CollectionEntry, CollectionEntryInput},
-    templates,
+    components::{
+        CollectionCreator, CollectionCreatorInput, CollectionCreatorOutput, CollectionData,
+        CollectionEntry, CollectionEntryInput, CollectionPage, CollectionPageInput,
+        CollectionPageOutput, DeckEditor, DeckEditorInput, DeckEditorOutput, DraftPage,
+        DraftPageInput, DraftPageOutput,
+    },
+    file_chooser, templates,
+};
+use crate::user_data::collection::{
+    Card, CardType, Collection, CollectionIndex, CollectionSummary, Deck, MetaData, StartingPool,
+    LAST_CHANGED_FORMAT,
 };
-use crate::user_data::collection::{Collection, LAST_CHANGED_FORMAT};
+
+const JSON_PATTERN: &str = "*.json";
+const CSV_PATTERN: &str = "*.csv";
 
 #[derive(Debug)]
 pub enum AppInput {
@@ -21,13 +47,55 @@ pub enum AppInput {
     CollectionSortDown(DynamicIndex),
     CollectionFilterBy(String),
     OpenCollection(String),
+    CloseCollection,
+    /// The collection with the given file name was saved elsewhere.
+    CollectionChanged(String),
+    OpenDraft(String),
+    /// Leaves the draft page, showing the collection that was drafted for.
+    CloseDraft(String),
+    FinishRound(String),
+    OpenDeckEditor(String),
+    /// Leaves the deck editor, showing the collection the decks belong to.
+    CloseDeckEditor(String),
+    DeckSearch(CardQuery),
+    /// Looks up the cards of a deck, checking them against the owned cards if given.
+    ResolveDeck(Box<Deck>, Option<HashMap<Card, u8>>),
+    ImportYdk(PathBuf, Option<HashMap<Card, u8>>),
     CollectionSaveChanges,
+    RepairCollection(String),
+    DeleteCollection(String),
+    ShowCollectionCreator,
+    CloseCollectionCreator,
+    CreateCollection(Box<Collection>, StartingPool),
+    /// Asks for a JSON collection or CSV card list to import.
+    ImportCollection,
+    ImportCollectionFile(PathBuf),
+    /// Creates a collection from the card pool of a `ydke://` URL in the clipboard.
+    PasteCardPool,
+    ImportCardPool(String),
+    /// Asks where to export the collection with the given file name.
+    ExportCollection(String),
+    ExportCollectionFile(String, PathBuf),
     UpdateButtonClicked,
 }
 
+#[derive(Debug)]
+pub enum AppCommandOutput {
+    UpdateProgress(UpdateStage),
+    /// Hands the connection back after the update, along with whether it succeeded.
+    UpdateFinished(rusqlite::Connection, bool),
+}
+
 pub struct App {
     collection_entries: FactoryVecDeque<CollectionEntry>,
+    collection_creator: Controller<CollectionCreator>,
+    collection_page: Controller<CollectionPage>,
+    draft_page: Controller<DraftPage>,
+    deck_editor: Controller<DeckEditor>,
     connection: OnceCell<rusqlite::Connection>,
+    /// Cancellation flag of the running database update.
+    update_cancel: Option<Arc<AtomicBool>>,
+    file_chooser: Option<gtk::FileChooserNative>,
 }
 
 #[relm4::component(pub)]
@@ -36,7 +104,7 @@ impl Component for App {
     type Input = AppInput;
     type Output = ();
     type Widgets = AppWidgets;
-    type CommandOutput = ();
+    type CommandOutput = AppCommandOutput;
 
     view! {
         adw::Window {
@@ -67,6 +135,16 @@ impl Component for App {
                                 set_icon_name: icon_name::SETTINGS,
                                 add_css_class: "circular",
                             },
+                            pack_end = &gtk::Button {
+                                set_icon_name: "document-open-symbolic",
+                                set_tooltip_text: Some("Import collection"),
+                                connect_clicked => AppInput::ImportCollection,
+                            },
+                            pack_end = &gtk::Button {
+                                set_icon_name: "edit-paste-symbolic",
+                                set_tooltip_text: Some("Import card pool from ydke:// URL in clipboard"),
+                                connect_clicked => AppInput::PasteCardPool,
+                            },
                         },
 
                         #[template_child]
@@ -78,9 +156,10 @@ impl Component for App {
                         adw::Banner {
                             set_title: "Database update available",
                             set_button_label: Some("Update"),
-                            set_revealed: get_or_log(db::new_version_available(), false),
+                            set_revealed: get_or_log(db::new_version_available(db::source::from_env().as_ref()), false),
                             connect_button_clicked => AppInput::UpdateButtonClicked,
                         },
+                        #[name = "no_collections_page"]
                         adw::Clamp {
                             set_visible: model.collection_entries.is_empty(),
                             set_orientation: Orientation::Horizontal,
@@ -95,10 +174,11 @@ impl Component for App {
                                     add_css_class: "pill",
                                     set_label: "Create collection",
                                     set_halign: Align::Center,
-                                    connect_clicked[sender] => move |_| {}
+                                    connect_clicked => AppInput::ShowCollectionCreator,
                                 }
                             }
                         },
+                        #[name = "collections_page"]
                         gtk::ScrolledWindow {
                             set_visible: !model.collection_entries.is_empty(),
                             set_min_content_height: 200,
@@ -129,7 +209,7 @@ impl Component for App {
                                         gtk::Button {
                                             set_icon_name: "list-add",
                                             add_css_class: "circular",
-                                            connect_clicked[sender] => move |_| {},
+                                            connect_clicked => AppInput::ShowCollectionCreator,
                                         },
                                     },
                                    #[local_ref]
@@ -139,11 +219,9 @@ impl Component for App {
                                 }
                             }
                         },
+                        #[name = "version_label"]
                         gtk::Label {
-                            set_label: &match get_or_log(db::get_local_version(), None) {
-                                Some(version) => format!("database version: {}", version),
-                                None => String::new(),
-                            },
+                            set_label: &version_label_text(),
                             set_halign: Align::End,
                             set_margin_end: 4,
                             add_css_class: "dim-label",
@@ -162,35 +240,17 @@ impl Component for App {
     ) -> ComponentParts<Self> {
         initialize_icons();
 
-        let collection_names = Collection::get_names();
+        let mut collection_index = CollectionIndex::load();
         let mut collection_entries_components = Vec::new();
-        for collection_name in collection_names {
-            let meta_data = Collection::get_metadata_from(&collection_name);
-            collection_entries_components.push(CollectionData::new(collection_name, meta_data));
+        for (collection_name, summary) in collection_index.summaries() {
+            collection_entries_components
+                .push(CollectionData::from_summary(collection_name, summary));
         }
-        collection_entries_components.sort_unstable_by(|first, second| {
-            let first_date = Utc
-                .datetime_from_str(&first.meta_data.last_changed, LAST_CHANGED_FORMAT)
-                .unwrap();
-            let second_date = Utc
-                .datetime_from_str(&second.meta_data.last_changed, LAST_CHANGED_FORMAT)
-                .unwrap();
-
-            if first.meta_data.pinned == second.meta_data.pinned {
-                if first_date < second_date {
-                    Ordering::Greater
-                } else if first_date != second_date {
-                    Ordering::Less
-                } else {
-                    Ordering::Equal
-                }
-            } else {
-                match first.meta_data.pinned {
-                    true => Ordering::Less,
-                    false => Ordering::Greater,
-                }
-            }
-        });
+        if let Err(error) = collection_index.save() {
+            log::warn!("Failed to save the collection index: {}", error);
+        }
+        collection_entries_components
+            .sort_unstable_by(|first, second| compare_entries(&first.meta_data, &second.meta_data));
 
         let collection_entries = FactoryVecDeque::from_iter(
             collection_entries_components,
@@ -198,15 +258,74 @@ impl Component for App {
             sender.input_sender(),
         );
 
+        let collection_creator =
+            CollectionCreator::builder()
+                .launch(())
+                .forward(sender.input_sender(), |message| match message {
+                    CollectionCreatorOutput::Create(collection, starting_pool) => {
+                        AppInput::CreateCollection(collection, starting_pool)
+                    }
+                    CollectionCreatorOutput::Cancel => AppInput::CloseCollectionCreator,
+                });
+
+        let collection_page =
+            CollectionPage::builder()
+                .launch(())
+                .forward(sender.input_sender(), |message| match message {
+                    CollectionPageOutput::Changed(file_name) => {
+                        AppInput::CollectionChanged(file_name)
+                    }
+                    CollectionPageOutput::Draft(file_name) => AppInput::OpenDraft(file_name),
+                    CollectionPageOutput::BuildDeck(file_name) => {
+                        AppInput::OpenDeckEditor(file_name)
+                    }
+                    CollectionPageOutput::Export(file_name) => {
+                        AppInput::ExportCollection(file_name)
+                    }
+                    CollectionPageOutput::Close => AppInput::CloseCollection,
+                });
+
+        let draft_page = DraftPage::builder().launch(()).forward(
+            sender.input_sender(),
+            |message| match message {
+                DraftPageOutput::Changed(file_name) => AppInput::CollectionChanged(file_name),
+                DraftPageOutput::RoundFinished(file_name) => AppInput::FinishRound(file_name),
+                DraftPageOutput::Close(file_name) => AppInput::CloseDraft(file_name),
+            },
+        );
+
+        let deck_editor = DeckEditor::builder().launch(()).forward(
+            sender.input_sender(),
+            |message| match message {
+                DeckEditorOutput::Search(query) => AppInput::DeckSearch(query),
+                DeckEditorOutput::Resolve(deck, owned) => AppInput::ResolveDeck(deck, owned),
+                DeckEditorOutput::ImportYdk(path, owned) => AppInput::ImportYdk(path, owned),
+                DeckEditorOutput::Changed(file_name) => AppInput::CollectionChanged(file_name),
+                DeckEditorOutput::Close(file_name) => AppInput::CloseDeckEditor(file_name),
+            },
+        );
+
         let connection = OnceCell::new();
         connection.set(conn).expect("OnceCell was just initialised");
 
         let model = Self {
             collection_entries,
+            collection_creator,
+            collection_page,
+            draft_page,
+            deck_editor,
             connection,
+            update_cancel: None,
+            file_chooser: None,
         };
         let collection_entry_box = model.collection_entries.widget();
         let widgets = view_output!();
+        widgets
+            .collection_picker_leaflet
+            .append(model.collection_creator.widget());
+        widgets.main_leaflet.append(model.collection_page.widget());
+        widgets.main_leaflet.append(model.draft_page.widget());
+        widgets.main_leaflet.append(model.deck_editor.widget());
 
         ComponentParts { model, widgets }
     }
@@ -215,7 +334,7 @@ impl Component for App {
         &mut self,
         widgets: &mut Self::Widgets,
         input: Self::Input,
-        _sender: ComponentSender<Self>,
+        sender: ComponentSender<Self>,
         _root: &Self::Root,
     ) {
         match input {
@@ -302,23 +421,609 @@ impl Component for App {
                 self.collection_entries.widget().set_visible(matched);
             }
             AppInput::OpenCollection(file_name) => {
-                todo!();
+                let Some(connection) = self.connection.get() else {
+                    log::error!("Cannot open a collection while the database is updating.");
+                    return;
+                };
+
+                let collection = match Collection::from_name(&file_name) {
+                    Ok(collection) => collection,
+                    Err(error) => {
+                        log::error!("{}", error);
+                        sender.input(AppInput::CollectionChanged(file_name));
+                        return;
+                    }
+                };
+                let cards = get_or_log(
+                    db::cards::get_for_collection(connection, &collection.cards),
+                    Vec::new(),
+                );
+
+                self.collection_page.emit(CollectionPageInput::Show(
+                    file_name,
+                    Box::new(collection),
+                    cards,
+                ));
+                widgets
+                    .main_leaflet
+                    .set_visible_child(self.collection_page.widget());
+            }
+            AppInput::CloseCollection => {
+                widgets
+                    .main_leaflet
+                    .navigate(adw::NavigationDirection::Back);
+            }
+            AppInput::CollectionChanged(file_name) => {
+                let Some(index) = self
+                    .collection_entries
+                    .iter()
+                    .position(|entry| entry.file == file_name)
+                else {
+                    return;
+                };
+
+                self.collection_entries.guard().remove(index);
+
+                self.insert_collection_entry(CollectionData::load(file_name));
+            }
+            AppInput::OpenDraft(file_name) => {
+                let Some(connection) = self.connection.get() else {
+                    log::error!("Cannot draft while the database is updating.");
+                    return;
+                };
+
+                let collection = match Collection::from_name(&file_name) {
+                    Ok(collection) => collection,
+                    Err(error) => {
+                        log::error!("{}", error);
+                        return;
+                    }
+                };
+                // Without offers the draft page would finish the round right away.
+                let offers = match ProgressionEngine::new(connection, &collection)
+                    .map_err(Box::<dyn Error>::from)
+                    .and_then(|engine| engine.remaining_offers(&collection))
+                {
+                    Ok(offers) => offers,
+                    Err(error) => {
+                        log::error!("Failed to generate draft offers: {}", error);
+                        self.collection_page
+                            .emit(CollectionPageInput::ShowError(format!(
+                                "Failed to generate draft offers: {}",
+                                error
+                            )));
+                        return;
+                    }
+                };
+
+                self.draft_page.emit(DraftPageInput::Start(
+                    file_name,
+                    Box::new(collection),
+                    offers,
+                ));
+                widgets
+                    .main_leaflet
+                    .set_visible_child(self.draft_page.widget());
+            }
+            AppInput::CloseDraft(file_name) => {
+                sender.input(AppInput::OpenCollection(file_name));
+            }
+            AppInput::FinishRound(file_name) => {
+                let Some(connection) = self.connection.get() else {
+                    log::error!("Cannot finish a round while the database is updating.");
+                    return;
+                };
+
+                match finish_round(connection, &file_name) {
+                    Ok(()) => sender.input(AppInput::CollectionChanged(file_name.clone())),
+                    Err(error) => log::error!("Failed to finish the round: {}", error),
+                }
+
+                sender.input(AppInput::OpenCollection(file_name));
+            }
+            AppInput::OpenDeckEditor(file_name) => {
+                let Some(connection) = self.connection.get() else {
+                    log::error!("Cannot build decks while the database is updating.");
+                    return;
+                };
+
+                let collection = match Collection::from_name(&file_name) {
+                    Ok(collection) => collection,
+                    Err(error) => {
+                        log::error!("{}", error);
+                        return;
+                    }
+                };
+                let owned: Vec<CardType> = collection.cards.keys().map(|card| card.id).collect();
+                let card_infos =
+                    get_or_log(db::cards::get_many(connection, &owned), HashMap::new());
+                let banlist = get_or_log(deck_banlist(connection, &collection), HashMap::new());
+
+                self.deck_editor.emit(DeckEditorInput::Show(
+                    file_name,
+                    Box::new(collection),
+                    card_infos,
+                    banlist,
+                ));
+                widgets
+                    .main_leaflet
+                    .set_visible_child(self.deck_editor.widget());
+            }
+            AppInput::CloseDeckEditor(file_name) => {
+                sender.input(AppInput::OpenCollection(file_name));
+            }
+            AppInput::DeckSearch(query) => {
+                let Some(connection) = self.connection.get() else {
+                    return;
+                };
+
+                self.deck_editor
+                    .emit(DeckEditorInput::SearchResults(get_or_log(
+                        query.run(connection),
+                        Vec::new(),
+                    )));
+            }
+            AppInput::ResolveDeck(deck, owned) => {
+                let Some(connection) = self.connection.get() else {
+                    log::error!("Cannot import decks while the database is updating.");
+                    return;
+                };
+
+                match DeckImport::resolve(connection, *deck, owned.as_ref()) {
+                    Ok(import) => self
+                        .deck_editor
+                        .emit(DeckEditorInput::Imported(Box::new(import))),
+                    Err(error) => log::error!("Failed to look up the imported deck: {}", error),
+                }
+            }
+            AppInput::ImportYdk(path, owned) => {
+                let Some(connection) = self.connection.get() else {
+                    log::error!("Cannot import decks while the database is updating.");
+                    return;
+                };
+
+                match import_ydk(connection, &path, owned.as_ref()) {
+                    Ok(import) => self
+                        .deck_editor
+                        .emit(DeckEditorInput::Imported(Box::new(import))),
+                    Err(error) => log::error!("Failed to import `{}`: {}", path.display(), error),
+                }
             }
             AppInput::CollectionSaveChanges => {
                 for entry in self.collection_entries.iter() {
                     if entry.pinned.has_changed() {
-                        let mut collection = Collection::from_name(&entry.file);
-                        collection.meta_data.pinned = entry.pinned.get();
-                        collection.save(&entry.file);
+                        let saved =
+                            Collection::from_name(&entry.file).and_then(|mut collection| {
+                                collection.meta_data.pinned = entry.pinned.get();
+                                collection.save(&entry.file)
+                            });
+
+                        if let Err(error) = saved {
+                            log::error!("Failed to save collection `{}`: {}", entry.file, error);
+                        }
+                    }
+                }
+            }
+            AppInput::RepairCollection(file_name) => {
+                if let Err(error) = Collection::restore_backup(&file_name) {
+                    log::error!("Failed to repair collection `{}`: {}", file_name, error);
+                }
+
+                sender.input(AppInput::CollectionChanged(file_name));
+            }
+            AppInput::DeleteCollection(file_name) => {
+                if let Err(error) = Collection::delete(&file_name) {
+                    log::error!("Failed to delete collection `{}`: {}", file_name, error);
+                    return;
+                }
+
+                if let Some(index) = self
+                    .collection_entries
+                    .iter()
+                    .position(|entry| entry.file == file_name)
+                {
+                    self.collection_entries.guard().remove(index);
+                }
+
+                let is_empty = self.collection_entries.is_empty();
+                widgets.no_collections_page.set_visible(is_empty);
+                widgets.collections_page.set_visible(!is_empty);
+            }
+            AppInput::ShowCollectionCreator => {
+                let set_names = match self.connection.get() {
+                    Some(connection) => get_or_log(db::sets::get_all(connection), Vec::new())
+                        .into_iter()
+                        .filter(|set| set.date.is_some())
+                        .map(|set| set.name)
+                        .collect(),
+                    None => Vec::new(),
+                };
+
+                self.collection_creator
+                    .emit(CollectionCreatorInput::Reset(set_names));
+                widgets
+                    .collection_picker_leaflet
+                    .set_visible_child(self.collection_creator.widget());
+            }
+            AppInput::CloseCollectionCreator => {
+                widgets
+                    .collection_picker_leaflet
+                    .navigate(adw::NavigationDirection::Back);
+            }
+            AppInput::CreateCollection(mut collection, starting_pool) => {
+                let Some(connection) = self.connection.get() else {
+                    log::error!("Cannot create a collection while the database is updating.");
+                    return;
+                };
+
+                if let Err(error) = starting_pool.apply(connection, &mut collection) {
+                    log::error!("Failed to create the starting cards: {}", error);
+                    return;
+                }
+
+                let file_name = Collection::new_file_name(&collection.meta_data.name);
+                if let Err(error) = collection.save(&file_name) {
+                    log::error!("Failed to save the new collection: {}", error);
+                    return;
+                }
+
+                self.insert_collection_entry(CollectionData::new(
+                    file_name,
+                    CollectionSummary::of(&collection),
+                ));
+
+                widgets.no_collections_page.set_visible(false);
+                widgets.collections_page.set_visible(true);
+                widgets
+                    .collection_picker_leaflet
+                    .navigate(adw::NavigationDirection::Back);
+            }
+            AppInput::ImportCollection => {
+                let sender = sender.clone();
+                let dialog = self.file_chooser.insert(file_chooser(
+                    &widgets.main_leaflet,
+                    "Import collection",
+                    gtk::FileChooserAction::Open,
+                    &[("Collections and card lists", &[JSON_PATTERN, CSV_PATTERN])],
+                    move |path| sender.input(AppInput::ImportCollectionFile(path)),
+                ));
+                dialog.show();
+            }
+            AppInput::ImportCollectionFile(path) => {
+                let file_name = match import_collection(&path) {
+                    Ok(file_name) => file_name,
+                    Err(error) => {
+                        log::error!("Failed to import `{}`: {}", path.display(), error);
+                        return;
                     }
+                };
+
+                self.insert_collection_entry(CollectionData::load(file_name));
+
+                widgets.no_collections_page.set_visible(false);
+                widgets.collections_page.set_visible(true);
+            }
+            AppInput::PasteCardPool => {
+                let sender = sender.clone();
+                widgets.main_leaflet.clipboard().read_text_async(
+                    None::<&gtk::gio::Cancellable>,
+                    move |text| match text {
+                        Ok(Some(text)) => sender.input(AppInput::ImportCardPool(text.into())),
+                        Ok(None) => log::error!("The clipboard does not contain text."),
+                        Err(error) => log::error!("Failed to read the clipboard: {}", error),
+                    },
+                );
+            }
+            AppInput::ImportCardPool(url) => {
+                let file_name = match import_card_pool(&url) {
+                    Ok(file_name) => file_name,
+                    Err(error) => {
+                        log::error!("Failed to import card pool: {}", error);
+                        return;
+                    }
+                };
+
+                self.insert_collection_entry(CollectionData::load(file_name));
+
+                widgets.no_collections_page.set_visible(false);
+                widgets.collections_page.set_visible(true);
+            }
+            AppInput::ExportCollection(file_name) => {
+                let sender = sender.clone();
+                let name = format!("{}.json", file_name);
+                let dialog = self.file_chooser.insert(file_chooser(
+                    &widgets.main_leaflet,
+                    "Export collection",
+                    gtk::FileChooserAction::Save,
+                    &[
+                        ("Collection (JSON)", &[JSON_PATTERN]),
+                        ("Card list (CSV)", &[CSV_PATTERN]),
+                    ],
+                    move |path| {
+                        sender.input(AppInput::ExportCollectionFile(file_name.clone(), path))
+                    },
+                ));
+                dialog.set_current_name(&name);
+                dialog.show();
+            }
+            AppInput::ExportCollectionFile(file_name, path) => {
+                let Some(connection) = self.connection.get() else {
+                    log::error!("Cannot export collections while the database is updating.");
+                    return;
+                };
+
+                if let Err(error) = export_collection(connection, &file_name, &path) {
+                    log::error!("Failed to export `{}`: {}", file_name, error);
                 }
             }
             AppInput::UpdateButtonClicked => {
-                let successful = get_or_log(db::update_or_restore(&mut self.connection), false);
+                if let Some(cancel) = &self.update_cancel {
+                    cancel.store(true, atomic::Ordering::Relaxed);
+                    widgets
+                        .update_banner
+                        .set_title("Cancelling database update…");
+                    return;
+                }
+
+                let Some(mut connection) = self.connection.take() else {
+                    return;
+                };
+
+                let cancel = Arc::new(AtomicBool::new(false));
+                self.update_cancel = Some(cancel.clone());
+                widgets.update_banner.set_button_label(Some("Cancel"));
+
+                sender.spawn_command(move |out| {
+                    let progress_out = out.clone();
+                    let mut progress = move |stage| {
+                        progress_out
+                            .send(AppCommandOutput::UpdateProgress(stage))
+                            .expect("Failed to send message `AppCommandOutput::UpdateProgress`.");
+                    };
+
+                    let mut cell = OnceCell::new();
+                    cell.set(connection).expect("OnceCell was just initialised");
+                    let successful = get_or_log(
+                        db::update_or_restore(
+                            &mut cell,
+                            db::source::from_env().as_ref(),
+                            &mut progress,
+                            &cancel,
+                        ),
+                        false,
+                    );
+                    // A failed restore leaves the cell empty.
+                    connection = match cell.take() {
+                        Some(connection) => connection,
+                        None => reopen_database(),
+                    };
+
+                    if successful {
+                        download_collection_images(&mut progress, &cancel);
+                    }
+
+                    out.send(AppCommandOutput::UpdateFinished(connection, successful))
+                        .expect("Failed to send message `AppCommandOutput::UpdateFinished`.");
+                });
+            }
+        }
+    }
+
+    fn update_cmd_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        message: Self::CommandOutput,
+        _sender: ComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        match message {
+            AppCommandOutput::UpdateProgress(stage) => {
+                widgets.update_banner.set_title(&stage.description());
+            }
+            AppCommandOutput::UpdateFinished(connection, successful) => {
+                self.connection
+                    .set(connection)
+                    .expect("Connection is only taken during updates");
+                self.update_cancel = None;
+                widgets.version_label.set_label(&version_label_text());
+
                 if successful {
                     widgets.update_banner.hide();
+                } else {
+                    widgets.update_banner.set_title("Database update failed");
+                    widgets.update_banner.set_button_label(Some("Retry"));
                 }
             }
         }
     }
 }
+
+impl App {
+    /// Inserts an entry at the position given by [`compare_entries`].
+    fn insert_collection_entry(&mut self, data: CollectionData) {
+        let index = self
+            .collection_entries
+            .iter()
+            .position(|entry| {
+                compare_entries(&data.meta_data, &entry_meta_data(entry)) == Ordering::Less
+            })
+            .unwrap_or(self.collection_entries.len());
+
+        self.collection_entries.guard().insert(index, data);
+    }
+}
+
+/// Pinned collections come first, then the most recently changed ones. Dates that
+/// cannot be parsed count as the oldest.
+fn compare_entries(first: &MetaData, second: &MetaData) -> Ordering {
+    let first_date = Utc
+        .datetime_from_str(&first.last_changed, LAST_CHANGED_FORMAT)
+        .ok();
+    let second_date = Utc
+        .datetime_from_str(&second.last_changed, LAST_CHANGED_FORMAT)
+        .ok();
+
+    if first.pinned == second.pinned {
+        second_date.cmp(&first_date)
+    } else {
+        match first.pinned {
+            true => Ordering::Less,
+            false => Ordering::Greater,
+        }
+    }
+}
+
+fn entry_meta_data(entry: &CollectionEntry) -> MetaData {
+    MetaData {
+        name: entry.name.clone(),
+        description: entry.description.clone(),
+        pinned: entry.pinned.get(),
+        last_changed: entry.last_modified.clone(),
+    }
+}
+
+/// Opens the packs of the collection's current round and advances it to the next one.
+fn finish_round(connection: &rusqlite::Connection, file_name: &str) -> Result<(), Box<dyn Error>> {
+    let mut collection = Collection::from_name(file_name)?;
+    ProgressionEngine::new(connection, &collection)?.complete_round(&mut collection)?;
+    collection.save(file_name)?;
+
+    Ok(())
+}
+
+/// TCG banlist of the collection's current round, or the current one if no snapshot
+/// covers that round.
+fn deck_banlist(
+    connection: &rusqlite::Connection,
+    collection: &Collection,
+) -> rusqlite::Result<HashMap<CardType, LimitStatus>> {
+    let engine = ProgressionEngine::new(connection, collection)?;
+
+    match engine.effective_banlist(engine.current_round(), Format::Tcg)? {
+        Some(banlist) => Ok(banlist),
+        None => db::banlist::get_banlist(connection, Format::Tcg),
+    }
+}
+
+fn is_csv(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"))
+}
+
+/// Saves a JSON collection or a CSV card list as a new collection named after the
+/// file and returns its file name.
+fn import_collection(path: &Path) -> Result<String, Box<dyn Error>> {
+    let text = fs::read_to_string(path)?;
+
+    let mut collection = match is_csv(path) {
+        true => {
+            let meta_data = MetaData {
+                name: path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                ..Default::default()
+            };
+            Collection::from_csv(meta_data, new_seed(), &text)?
+        }
+        false => Collection::from_json(&text)?,
+    };
+
+    let file_name = Collection::new_file_name(&collection.meta_data.name);
+    collection.save(&file_name)?;
+
+    Ok(file_name)
+}
+
+/// Saves a new collection with the card pool of a `ydke://` URL and returns its file name.
+fn import_card_pool(url: &str) -> Result<String, Box<dyn Error>> {
+    let meta_data = MetaData {
+        name: "Imported card pool".to_string(),
+        ..Default::default()
+    };
+    let mut collection = Collection::from_cards(meta_data, new_seed(), ydke_to_cards(url)?);
+
+    let file_name = Collection::new_file_name(&collection.meta_data.name);
+    collection.save(&file_name)?;
+
+    Ok(file_name)
+}
+
+/// Writes the collection as a CSV card list if `path` ends in `.csv` and as JSON
+/// otherwise.
+fn export_collection(
+    connection: &rusqlite::Connection,
+    file_name: &str,
+    path: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let collection = Collection::from_name(file_name)?;
+
+    let text = match is_csv(path) {
+        true => collection.to_csv(connection)?,
+        false => collection.to_json()?,
+    };
+    fs::write(path, text)?;
+
+    Ok(())
+}
+
+/// Opens the database again after an update failed to restore it. Falls back to the
+/// file without migrating it, and then to an empty in-memory database, so the app always
+/// gets a connection back.
+fn reopen_database() -> rusqlite::Connection {
+    match db::migrations::open(files::DB.as_path()) {
+        Ok(connection) => return connection,
+        Err(error) => log::error!("Failed to reopen the database: {}", error),
+    }
+
+    match rusqlite::Connection::open(files::DB.as_path()) {
+        Ok(connection) => return connection,
+        Err(error) => log::error!("Failed to open the database file: {}", error),
+    }
+
+    rusqlite::Connection::open_in_memory().expect("SQLite failed to open an in-memory database.")
+}
+
+fn version_label_text() -> String {
+    match get_or_log(db::get_local_version(), None) {
+        Some(version) => format!("database version: {}", version),
+        None => String::new(),
+    }
+}
+
+/// Downloads missing images of all cards in the saved collections. Stops early if
+/// `cancel` is set, keeping the images downloaded so far.
+fn download_collection_images(progress: &mut dyn FnMut(UpdateStage), cancel: &AtomicBool) {
+    let ids: Vec<CardType> = Collection::get_names()
+        .iter()
+        .filter_map(|name| Collection::from_name(name).ok())
+        .flat_map(|collection| collection.cards.into_keys())
+        .map(|card| card.id)
+        .collect::<HashSet<CardType>>()
+        .into_iter()
+        .collect();
+
+    let manager = ImageManager::from_env();
+    match manager.verify_cache(ImageKind::Full) {
+        Ok(removed) if !removed.is_empty() => {
+            log::info!("Removed {} broken cached images.", removed.len())
+        }
+        Ok(_) => (),
+        Err(error) => log::warn!("Failed to verify the image cache: {}", error),
+    }
+
+    progress(UpdateStage::DownloadingImages {
+        done: 0,
+        total: ids.len(),
+    });
+    let report = manager.fetch_missing(&ids, ImageKind::Full, cancel, |image| {
+        progress(UpdateStage::DownloadingImages {
+            done: image.done,
+            total: image.total,
+        })
+    });
+
+    for (id, error) in report.failed {
+        log::warn!("Failed to fetch the image of card {}: {}", id, error);
+    }
+}
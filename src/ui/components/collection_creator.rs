@@ -0,0 +1,345 @@
+use adw::{gtk::Align, prelude::*};
+use relm4::prelude::*;
+
+use crate::draft::new_seed;
+use crate::ui::templates;
+use crate::user_data::collection::{
+    Collection, DraftFormat, DuplicatePolicy, MetaData, PoolSource, Progression, StartingPool,
+};
+
+#[derive(Debug)]
+pub enum CollectionCreatorInput {
+    /// Clears the form and offers the given sets, oldest first.
+    Reset(Vec<String>),
+    Validate,
+    Create,
+    Cancel,
+}
+
+#[derive(Debug)]
+pub enum CollectionCreatorOutput {
+    Create(Box<Collection>, StartingPool),
+    Cancel,
+}
+
+#[derive(Debug)]
+pub struct CollectionCreator {
+    set_names: Vec<String>,
+}
+
+#[relm4::component(pub)]
+impl Component for CollectionCreator {
+    type Init = ();
+    type Input = CollectionCreatorInput;
+    type Output = CollectionCreatorOutput;
+    type CommandOutput = ();
+    type Widgets = CollectionCreatorWidgets;
+
+    view! {
+        #[root]
+        gtk::Box {
+            set_orientation: gtk::Orientation::Vertical,
+
+            #[template]
+            templates::Page {
+                #[template_child]
+                back_button {
+                    connect_clicked => CollectionCreatorInput::Cancel,
+                },
+
+                #[template_child]
+                header {
+                    pack_end: create_button = &gtk::Button {
+                        set_label: "Create",
+                        add_css_class: "suggested-action",
+                        set_sensitive: false,
+                        connect_clicked => CollectionCreatorInput::Create,
+                    },
+                },
+
+                #[template_child]
+                window_title {
+                    set_title: "New collection",
+                },
+
+                adw::PreferencesPage {
+                    set_vexpand: true,
+
+                    adw::PreferencesGroup {
+                        #[name = "name_row"]
+                        adw::EntryRow {
+                            set_title: "Name",
+                            connect_changed => CollectionCreatorInput::Validate,
+                        },
+                        #[name = "description_row"]
+                        adw::EntryRow {
+                            set_title: "Description",
+                        },
+                    },
+
+                    adw::PreferencesGroup {
+                        set_title: "Card pool",
+
+                        #[name = "start_set_row"]
+                        adw::ComboRow {
+                            set_title: "First set",
+                            set_subtitle: "Sets become available in release order from here",
+                        },
+                        #[name = "starting_pool_row"]
+                        adw::ComboRow {
+                            set_title: "Starting cards",
+                            set_model: Some(&gtk::StringList::new(&["None", "Starter deck", "Packs"])),
+                            connect_selected_notify => CollectionCreatorInput::Validate,
+                        },
+                        #[name = "starter_deck_row"]
+                        adw::ComboRow {
+                            set_title: "Starter deck",
+                            set_visible: false,
+                        },
+                        #[name = "starting_packs_row"]
+                        adw::ActionRow {
+                            set_title: "Starting packs",
+                            set_visible: false,
+                            add_suffix: starting_packs_spin = &gtk::SpinButton::with_range(1.0, 100.0, 1.0) {
+                                set_value: 10.0,
+                                set_valign: Align::Center,
+                            },
+                        },
+                        adw::ActionRow {
+                            set_title: "Sets per round",
+                            add_suffix: sets_per_round_spin = &gtk::SpinButton::with_range(1.0, 10.0, 1.0) {
+                                set_valign: Align::Center,
+                            },
+                        },
+                        adw::ActionRow {
+                            set_title: "Packs per round",
+                            add_suffix: packs_per_round_spin = &gtk::SpinButton::with_range(0.0, 100.0, 1.0) {
+                                set_valign: Align::Center,
+                            },
+                        },
+                    },
+
+                    adw::PreferencesGroup {
+                        set_title: "Drafting",
+
+                        #[name = "pool_row"]
+                        adw::ComboRow {
+                            set_title: "Offered cards",
+                            set_model: Some(&gtk::StringList::new(&["Packs of new sets", "Archetypes", "All released cards"])),
+                            connect_selected_notify => CollectionCreatorInput::Validate,
+                        },
+                        #[name = "archetypes_row"]
+                        adw::EntryRow {
+                            set_title: "Archetypes (comma separated)",
+                            set_visible: false,
+                            connect_changed => CollectionCreatorInput::Validate,
+                        },
+                        #[name = "duplicates_row"]
+                        adw::ComboRow {
+                            set_title: "Duplicates",
+                            set_model: Some(&gtk::StringList::new(&["Allowed", "Unique within an offer", "Exclude owned cards"])),
+                        },
+                        adw::ActionRow {
+                            set_title: "Offers per round",
+                            add_suffix: offers_spin = &gtk::SpinButton::with_range(0.0, 20.0, 1.0) {
+                                set_valign: Align::Center,
+                            },
+                        },
+                        adw::ActionRow {
+                            set_title: "Boxes per offer",
+                            add_suffix: boxes_spin = &gtk::SpinButton::with_range(1.0, 10.0, 1.0) {
+                                set_valign: Align::Center,
+                            },
+                        },
+                        adw::ActionRow {
+                            set_title: "Cards per box",
+                            add_suffix: cards_spin = &gtk::SpinButton::with_range(1.0, 20.0, 1.0) {
+                                set_valign: Align::Center,
+                            },
+                        },
+                        adw::ActionRow {
+                            set_title: "Picks per offer",
+                            add_suffix: picks_spin = &gtk::SpinButton::with_range(1.0, 10.0, 1.0) {
+                                set_valign: Align::Center,
+                            },
+                        },
+                    },
+
+                    adw::PreferencesGroup {
+                        set_title: "Randomness",
+
+                        #[name = "seed_row"]
+                        adw::EntryRow {
+                            set_title: "Seed",
+                            set_input_purpose: gtk::InputPurpose::Digits,
+                            connect_changed => CollectionCreatorInput::Validate,
+                        },
+                    },
+                },
+            }
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: &Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = Self {
+            set_names: Vec::new(),
+        };
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        input: Self::Input,
+        sender: ComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        match input {
+            CollectionCreatorInput::Reset(set_names) => {
+                let set_list: Vec<&str> = set_names.iter().map(String::as_str).collect();
+                let start_set_list: Vec<&str> =
+                    ["Oldest set"].into_iter().chain(set_list.clone()).collect();
+                widgets
+                    .start_set_row
+                    .set_model(Some(&gtk::StringList::new(&start_set_list)));
+                widgets
+                    .starter_deck_row
+                    .set_model(Some(&gtk::StringList::new(&set_list)));
+                self.set_names = set_names;
+
+                let progression = Progression::default();
+                let draft_format = DraftFormat::default();
+
+                widgets.name_row.set_text("");
+                widgets.description_row.set_text("");
+                widgets.starting_pool_row.set_selected(0);
+                widgets.starting_packs_spin.set_value(10.0);
+                widgets
+                    .sets_per_round_spin
+                    .set_value(progression.sets_per_round as f64);
+                widgets
+                    .packs_per_round_spin
+                    .set_value(progression.packs_per_round as f64);
+                widgets.pool_row.set_selected(0);
+                widgets.archetypes_row.set_text("");
+                widgets.duplicates_row.set_selected(0);
+                widgets
+                    .offers_spin
+                    .set_value(draft_format.offers_per_round as f64);
+                widgets
+                    .boxes_spin
+                    .set_value(draft_format.boxes_per_offer as f64);
+                widgets
+                    .cards_spin
+                    .set_value(draft_format.cards_per_box as f64);
+                widgets
+                    .picks_spin
+                    .set_value(draft_format.picks_per_offer as f64);
+                widgets.seed_row.set_text(&new_seed().to_string());
+            }
+            CollectionCreatorInput::Validate => {
+                widgets
+                    .starter_deck_row
+                    .set_visible(widgets.starting_pool_row.selected() == 1);
+                widgets
+                    .starting_packs_row
+                    .set_visible(widgets.starting_pool_row.selected() == 2);
+                widgets
+                    .archetypes_row
+                    .set_visible(widgets.pool_row.selected() == 1);
+
+                let seed_valid = widgets.seed_row.text().trim().parse::<u64>().is_ok();
+                match seed_valid {
+                    true => widgets.seed_row.remove_css_class("error"),
+                    false => widgets.seed_row.add_css_class("error"),
+                }
+
+                let archetypes_valid =
+                    widgets.pool_row.selected() != 1 || !archetypes(widgets).is_empty();
+
+                widgets.create_button.set_sensitive(
+                    !widgets.name_row.text().trim().is_empty() && seed_valid && archetypes_valid,
+                );
+            }
+            CollectionCreatorInput::Create => {
+                let Ok(seed) = widgets.seed_row.text().trim().parse::<u64>() else {
+                    return;
+                };
+
+                let meta_data = MetaData {
+                    name: widgets.name_row.text().trim().to_string(),
+                    description: widgets.description_row.text().trim().to_string(),
+                    ..Default::default()
+                };
+
+                let mut collection = Collection::new(meta_data, seed);
+                collection.progression = Progression {
+                    start_set: (widgets.start_set_row.selected() as usize)
+                        .checked_sub(1)
+                        .and_then(|index| self.set_names.get(index).cloned()),
+                    sets_per_round: widgets.sets_per_round_spin.value() as u16,
+                    packs_per_round: widgets.packs_per_round_spin.value() as u16,
+                    ..Default::default()
+                };
+                collection.draft_format = DraftFormat {
+                    boxes_per_offer: widgets.boxes_spin.value() as usize,
+                    cards_per_box: widgets.cards_spin.value() as usize,
+                    picks_per_offer: (widgets.picks_spin.value() as usize)
+                        .min(widgets.boxes_spin.value() as usize),
+                    offers_per_round: widgets.offers_spin.value() as u16,
+                    pool: match widgets.pool_row.selected() {
+                        1 => PoolSource::Archetypes(archetypes(widgets)),
+                        2 => PoolSource::CardPool,
+                        _ => PoolSource::Sets,
+                    },
+                    duplicates: match widgets.duplicates_row.selected() {
+                        1 => DuplicatePolicy::UniqueInOffer,
+                        2 => DuplicatePolicy::ExcludeOwned,
+                        _ => DuplicatePolicy::Allow,
+                    },
+                };
+
+                let starting_pool = match widgets.starting_pool_row.selected() {
+                    1 => match self
+                        .set_names
+                        .get(widgets.starter_deck_row.selected() as usize)
+                    {
+                        Some(set_name) => StartingPool::StarterDeck(set_name.clone()),
+                        None => StartingPool::Empty,
+                    },
+                    2 => StartingPool::Packs(widgets.starting_packs_spin.value() as u16),
+                    _ => StartingPool::Empty,
+                };
+
+                sender
+                    .output(CollectionCreatorOutput::Create(
+                        Box::new(collection),
+                        starting_pool,
+                    ))
+                    .expect("Failed to send message `CollectionCreatorOutput::Create`.");
+            }
+            CollectionCreatorInput::Cancel => {
+                sender
+                    .output(CollectionCreatorOutput::Cancel)
+                    .expect("Failed to send message `CollectionCreatorOutput::Cancel`.");
+            }
+        }
+    }
+}
+
+fn archetypes(widgets: &CollectionCreatorWidgets) -> Vec<String> {
+    widgets
+        .archetypes_row
+        .text()
+        .split(',')
+        .map(|archetype| archetype.trim().to_string())
+        .filter(|archetype| !archetype.is_empty())
+        .collect()
+}
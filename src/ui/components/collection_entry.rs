@@ -2,8 +2,12 @@ use adw::prelude::*;
 use gtk::glib;
 use relm4::prelude::*;
 
+use chrono::prelude::*;
+
 use crate::ui::AppInput;
-use crate::user_data::collection::MetaData;
+use crate::user_data::collection::{
+    Collection, CollectionError, CollectionSummary, MetaData, LAST_CHANGED_FORMAT,
+};
 
 #[derive(Debug, PartialEq)]
 pub struct TrackedBool {
@@ -39,17 +43,67 @@ impl TrackedBool {
     }
 }
 
+/// Problem with a collection file that could not be read.
+#[derive(Debug)]
+pub struct BrokenCollection {
+    pub has_backup: bool,
+}
+
 #[derive(Debug)]
 pub struct CollectionData {
     pub file_name: String,
     pub meta_data: MetaData,
+    pub card_count: u32,
+    pub round: u16,
+    pub broken: Option<BrokenCollection>,
 }
 
 impl CollectionData {
-    pub fn new(file_name: String, meta_data: MetaData) -> Self {
+    pub fn new(file_name: String, summary: CollectionSummary) -> Self {
         Self {
             file_name,
-            meta_data,
+            meta_data: summary.meta_data,
+            card_count: summary.card_count,
+            round: summary.round,
+            broken: None,
+        }
+    }
+
+    /// Entry for a file that failed to load, showing the file name and the error. It is
+    /// sorted like a collection that was just changed.
+    pub fn broken(file_name: String, error: &CollectionError) -> Self {
+        Self {
+            meta_data: MetaData {
+                name: file_name.clone(),
+                description: error.to_string(),
+                pinned: false,
+                last_changed: format!("{}", Utc::now().format(LAST_CHANGED_FORMAT)),
+            },
+            card_count: 0,
+            round: 0,
+            broken: Some(BrokenCollection {
+                has_backup: Collection::has_backup(&file_name),
+            }),
+            file_name,
+        }
+    }
+
+    /// Loads the metadata of the collection file called `file_name`.
+    pub fn load(file_name: String) -> Self {
+        let summary = Collection::read_summary(&file_name);
+        Self::from_summary(file_name, summary)
+    }
+
+    pub fn from_summary(
+        file_name: String,
+        summary: Result<CollectionSummary, CollectionError>,
+    ) -> Self {
+        match summary {
+            Ok(summary) => Self::new(file_name, summary),
+            Err(error) => {
+                log::warn!("Failed to load collection `{}`: {}", file_name, error);
+                Self::broken(file_name, &error)
+            }
         }
     }
 }
@@ -61,6 +115,8 @@ pub enum CollectionEntryInput {
     CursorEntered,
     CursorLeft,
     Open,
+    Repair,
+    Delete,
 }
 
 #[derive(Debug)]
@@ -70,6 +126,8 @@ pub enum CollectionEntryOutput {
     FilterBy(String),
     OpenCollection(String),
     SaveChanges,
+    Repair(String),
+    Delete(String),
 }
 
 #[derive(Debug)]
@@ -78,7 +136,11 @@ pub struct CollectionEntry {
     pub name: String,
     pub description: String,
     pub last_modified: String,
+    pub card_count: u32,
+    pub round: u16,
     pub pinned: TrackedBool,
+    pub broken: bool,
+    has_backup: bool,
     index: DynamicIndex,
 }
 
@@ -112,7 +174,34 @@ impl FactoryComponent for CollectionEntry {
 
                 controller
             },
+            add_prefix = &gtk::Image {
+                set_icon_name: Some("dialog-warning-symbolic"),
+                set_visible: self.broken,
+                add_css_class: "warning",
+            },
+            add_suffix = &gtk::Button {
+                set_label: "Repair",
+                set_tooltip_text: Some("Restore the last readable version"),
+                set_visible: self.has_backup,
+                set_valign: gtk::Align::Center,
+                connect_clicked => CollectionEntryInput::Repair,
+            },
+            add_suffix = &gtk::Button {
+                set_icon_name: "user-trash-symbolic",
+                set_tooltip_text: Some("Delete"),
+                set_visible: self.broken,
+                set_valign: gtk::Align::Center,
+                add_css_class: "flat",
+                connect_clicked => CollectionEntryInput::Delete,
+            },
+            add_suffix = &gtk::Label {
+                set_label: &format!("{} cards · round {}", self.card_count, self.round),
+                set_visible: !self.broken,
+                add_css_class: "dim-label",
+                add_css_class: "caption",
+            },
             add_suffix: star_button = &gtk::Button {
+                set_visible: !self.broken,
                 set_icon_name?: match self.pinned.get() {
                     true => Some("starred-symbolic"),
                     false => None,
@@ -131,6 +220,8 @@ impl FactoryComponent for CollectionEntry {
             CollectionEntryOutput::FilterBy(text) => AppInput::CollectionFilterBy(text),
             CollectionEntryOutput::OpenCollection(name) => AppInput::OpenCollection(name),
             CollectionEntryOutput::SaveChanges => AppInput::CollectionSaveChanges,
+            CollectionEntryOutput::Repair(name) => AppInput::RepairCollection(name),
+            CollectionEntryOutput::Delete(name) => AppInput::DeleteCollection(name),
         })
     }
 
@@ -140,7 +231,11 @@ impl FactoryComponent for CollectionEntry {
             name: value.meta_data.name,
             description: value.meta_data.description,
             last_modified: value.meta_data.last_changed,
+            card_count: value.card_count,
+            round: value.round,
             pinned: TrackedBool::new(value.meta_data.pinned),
+            broken: value.broken.is_some(),
+            has_backup: value.broken.is_some_and(|broken| broken.has_backup),
             index: index.clone(),
         }
     }
@@ -174,7 +269,15 @@ impl FactoryComponent for CollectionEntry {
                 }
             }
             CollectionEntryInput::Open => {
-                sender.output(CollectionEntryOutput::OpenCollection(self.file.clone()));
+                if !self.broken {
+                    sender.output(CollectionEntryOutput::OpenCollection(self.file.clone()));
+                }
+            }
+            CollectionEntryInput::Repair => {
+                sender.output(CollectionEntryOutput::Repair(self.file.clone()));
+            }
+            CollectionEntryInput::Delete => {
+                sender.output(CollectionEntryOutput::Delete(self.file.clone()));
             }
         }
     }
@@ -0,0 +1,330 @@
+use adw::{gtk::Align, prelude::*};
+use chrono::prelude::*;
+use gtk::Orientation;
+use relm4::prelude::*;
+
+use crate::data::images::load_card;
+use crate::db::cards::CardInfo;
+use crate::deck_formats::collection_to_ydke;
+use crate::ui::{templates, widgets::RowSplitBox};
+use crate::user_data::collection::{Change, Collection, LAST_CHANGED_FORMAT};
+
+#[derive(Debug)]
+pub enum CollectionPageInput {
+    /// Shows a collection along with its cards sorted for display.
+    Show(String, Box<Collection>, Vec<(CardInfo, u8)>),
+    ShowError(String),
+    SaveMetaData,
+    Draft,
+    BuildDeck,
+    Export,
+    /// Copies the card pool as a `ydke://` URL.
+    CopyYdke,
+    Back,
+}
+
+#[derive(Debug)]
+pub enum CollectionPageOutput {
+    /// The collection with the given file name was saved.
+    Changed(String),
+    Draft(String),
+    BuildDeck(String),
+    /// Asks where to export the collection with the given file name.
+    Export(String),
+    Close,
+}
+
+#[derive(Debug)]
+pub struct CollectionPage {
+    file_name: String,
+    collection: Collection,
+}
+
+#[relm4::component(pub)]
+impl Component for CollectionPage {
+    type Init = ();
+    type Input = CollectionPageInput;
+    type Output = CollectionPageOutput;
+    type CommandOutput = ();
+    type Widgets = CollectionPageWidgets;
+
+    view! {
+        #[root]
+        gtk::Box {
+            set_orientation: Orientation::Vertical,
+
+            #[template]
+            templates::Page {
+                #[template_child]
+                back_button {
+                    connect_clicked => CollectionPageInput::Back,
+                },
+
+                #[template_child]
+                header {
+                    pack_end = &gtk::Button {
+                        set_icon_name: "edit-copy-symbolic",
+                        set_tooltip_text: Some("Copy card pool as ydke:// URL"),
+                        connect_clicked => CollectionPageInput::CopyYdke,
+                    },
+                    pack_end = &gtk::Button {
+                        set_icon_name: "document-save-symbolic",
+                        set_tooltip_text: Some("Export collection"),
+                        connect_clicked => CollectionPageInput::Export,
+                    },
+                },
+
+                #[template_child]
+                window_title {
+                    #[watch]
+                    set_title: &model.collection.meta_data.name,
+                    #[watch]
+                    set_subtitle: &model.collection.meta_data.description,
+                },
+
+                #[name = "error_banner"]
+                adw::Banner {
+                    set_button_label: Some("Dismiss"),
+                    connect_button_clicked: |banner| banner.set_revealed(false),
+                },
+
+                gtk::ScrolledWindow {
+                    set_vexpand: true,
+                    set_hscrollbar_policy: gtk::PolicyType::Never,
+
+                    adw::Clamp {
+                        set_orientation: Orientation::Horizontal,
+                        set_maximum_size: 1200,
+
+                        gtk::Box::new(Orientation::Vertical, 12) {
+                            set_margin_all: 12,
+
+                            gtk::Box::new(Orientation::Horizontal, 6) {
+                                set_halign: Align::Center,
+
+                                gtk::Button {
+                                    set_label: "Draft",
+                                    add_css_class: "suggested-action",
+                                    add_css_class: "pill",
+                                    connect_clicked => CollectionPageInput::Draft,
+                                },
+                                gtk::Button {
+                                    set_label: "Build deck",
+                                    add_css_class: "pill",
+                                    connect_clicked => CollectionPageInput::BuildDeck,
+                                },
+                            },
+
+                            adw::PreferencesGroup {
+                                set_title: "Details",
+
+                                #[name = "name_row"]
+                                adw::EntryRow {
+                                    set_title: "Name",
+                                    set_show_apply_button: true,
+                                    connect_apply => CollectionPageInput::SaveMetaData,
+                                },
+                                #[name = "description_row"]
+                                adw::EntryRow {
+                                    set_title: "Description",
+                                    set_show_apply_button: true,
+                                    connect_apply => CollectionPageInput::SaveMetaData,
+                                },
+                                adw::ActionRow {
+                                    set_title: "Cards",
+                                    #[watch]
+                                    set_subtitle: &format!(
+                                        "{} ({} unique)",
+                                        model.collection.cards.values().map(|quantity| *quantity as u32).sum::<u32>(),
+                                        model.collection.cards.len(),
+                                    ),
+                                },
+                                adw::ActionRow {
+                                    set_title: "Round",
+                                    #[watch]
+                                    set_subtitle: &model.collection.progression.current_round().to_string(),
+                                },
+                                adw::ActionRow {
+                                    set_title: "Last changed",
+                                    #[watch]
+                                    set_subtitle: &display_date(&model.collection.meta_data.last_changed),
+                                },
+                            },
+
+                            gtk::Label::new(Some("cards")) {
+                                add_css_class: "heading",
+                                set_halign: Align::Start,
+                            },
+                            #[name = "card_grid"]
+                            RowSplitBox::new(120, 175, 6, 6) {},
+
+                            gtk::Label::new(Some("history")) {
+                                add_css_class: "heading",
+                                set_halign: Align::Start,
+                            },
+                            #[name = "history_list"]
+                            gtk::ListBox {
+                                add_css_class: "boxed-list",
+                                set_selection_mode: gtk::SelectionMode::None,
+                            },
+                        },
+                    },
+                },
+            },
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: &Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = Self {
+            file_name: String::new(),
+            collection: Collection::default(),
+        };
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        input: Self::Input,
+        sender: ComponentSender<Self>,
+        root: &Self::Root,
+    ) {
+        match input {
+            CollectionPageInput::Show(file_name, collection, cards) => {
+                self.file_name = file_name;
+                self.collection = *collection;
+
+                widgets.error_banner.set_revealed(false);
+                widgets.name_row.set_text(&self.collection.meta_data.name);
+                widgets
+                    .description_row
+                    .set_text(&self.collection.meta_data.description);
+
+                widgets.card_grid.clear();
+                for (card, quantity) in cards {
+                    widgets.card_grid.insert(card_cell(&card, quantity).into());
+                }
+
+                while let Some(row) = widgets.history_list.first_child() {
+                    widgets.history_list.remove(&row);
+                }
+                for change in self.collection.changes.iter().rev() {
+                    if let Some(row) = history_row(change) {
+                        widgets.history_list.append(&row);
+                    }
+                }
+                widgets
+                    .history_list
+                    .set_visible(widgets.history_list.first_child().is_some());
+            }
+            CollectionPageInput::ShowError(message) => {
+                widgets.error_banner.set_title(&message);
+                widgets.error_banner.set_revealed(true);
+            }
+            CollectionPageInput::SaveMetaData => {
+                let name = widgets.name_row.text().trim().to_string();
+                if name.is_empty() {
+                    widgets.name_row.set_text(&self.collection.meta_data.name);
+                    return;
+                }
+
+                let saved = self.collection.meta_data.clone();
+                self.collection.meta_data.name = name;
+                self.collection.meta_data.description =
+                    widgets.description_row.text().trim().to_string();
+                if let Err(error) = self.collection.save(&self.file_name) {
+                    log::error!("Failed to save collection `{}`: {}", self.file_name, error);
+
+                    self.collection.meta_data = saved;
+                    sender.input(CollectionPageInput::ShowError(format!(
+                        "Failed to save the collection: {}",
+                        error
+                    )));
+                    return;
+                }
+
+                widgets.error_banner.set_revealed(false);
+                sender
+                    .output(CollectionPageOutput::Changed(self.file_name.clone()))
+                    .expect("Failed to send message `CollectionPageOutput::Changed`.");
+            }
+            CollectionPageInput::Draft => {
+                sender
+                    .output(CollectionPageOutput::Draft(self.file_name.clone()))
+                    .expect("Failed to send message `CollectionPageOutput::Draft`.");
+            }
+            CollectionPageInput::BuildDeck => {
+                sender
+                    .output(CollectionPageOutput::BuildDeck(self.file_name.clone()))
+                    .expect("Failed to send message `CollectionPageOutput::BuildDeck`.");
+            }
+            CollectionPageInput::Export => {
+                sender
+                    .output(CollectionPageOutput::Export(self.file_name.clone()))
+                    .expect("Failed to send message `CollectionPageOutput::Export`.");
+            }
+            CollectionPageInput::CopyYdke => root
+                .clipboard()
+                .set_text(&collection_to_ydke(&self.collection)),
+            CollectionPageInput::Back => {
+                sender
+                    .output(CollectionPageOutput::Close)
+                    .expect("Failed to send message `CollectionPageOutput::Close`.");
+            }
+        }
+
+        self.update_view(widgets, sender);
+    }
+}
+
+/// Card image with its quantity in the bottom right corner.
+fn card_cell(card: &CardInfo, quantity: u8) -> gtk::Overlay {
+    let overlay = gtk::Overlay::new();
+    overlay.set_tooltip_text(Some(&card.name));
+    overlay.set_child(Some(&load_card(card.id)));
+
+    if quantity > 1 {
+        let label = gtk::Label::new(Some(&format!("×{}", quantity)));
+        label.set_halign(Align::End);
+        label.set_valign(Align::End);
+        label.set_margin_all(4);
+        label.add_css_class("osd");
+        label.add_css_class("caption-heading");
+        overlay.add_overlay(&label);
+    }
+
+    overlay
+}
+
+fn history_row(change: &Change) -> Option<adw::ActionRow> {
+    let (title, content) = match change {
+        Change::Add(content) => ("Added", content),
+        Change::Remove(content) => ("Removed", content),
+        Change::None => return None,
+    };
+
+    let mut subtitle = display_date(&content.date);
+    if let Some(round) = content.round {
+        subtitle.push_str(&format!(" · round {}", round));
+    }
+
+    let row = adw::ActionRow::new();
+    row.set_title(&format!("{} {} cards", title, content.cards.len()));
+    row.set_subtitle(&subtitle);
+
+    Some(row)
+}
+
+fn display_date(date: &str) -> String {
+    match Utc.datetime_from_str(date, LAST_CHANGED_FORMAT) {
+        Ok(date) => date.with_timezone(&Local).format("%x %R").to_string(),
+        Err(_) => date.to_string(),
+    }
+}
@@ -0,0 +1,642 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use adw::{gtk::Align, prelude::*};
+use gtk::Orientation;
+use relm4::prelude::*;
+
+use crate::db::banlist::LimitStatus;
+use crate::db::cards::CardInfo;
+use crate::db::search::CardQuery;
+use crate::deck_formats::{parse_ydke, to_ydke, write_ydk, DeckImport, YDK_EXTENSION};
+use crate::legality::{validate_with_infos, Violation};
+use crate::ui::{file_chooser, templates};
+use crate::user_data::collection::{Card, CardType, Collection, Deck, DeckSection};
+
+const SEARCH_PAGE_SIZE: u32 = 50;
+const YDK_PATTERN: &str = "*.ydk";
+
+#[derive(Debug)]
+pub enum DeckEditorInput {
+    /// Shows the decks of a collection along with the info of every card it owns and
+    /// the banlist to check decks against.
+    Show(
+        String,
+        Box<Collection>,
+        HashMap<CardType, CardInfo>,
+        HashMap<CardType, LimitStatus>,
+    ),
+    SearchChanged(String),
+    SearchResults(Vec<CardInfo>),
+    Add(DeckSection, CardType),
+    Remove(DeckSection, CardType),
+    SelectDeck(u32),
+    NewDeck,
+    RenameDeck,
+    DeleteDeck,
+    /// Whether imported decks are checked against the collection.
+    CheckOwned(bool),
+    ImportYdk,
+    ExportYdk,
+    ImportFile(PathBuf),
+    ExportFile(PathBuf),
+    CopyYdke,
+    PasteYdke,
+    /// Imports a deck from a `ydke://` URL.
+    ImportYdke(String),
+    /// Adds a deck whose cards were looked up after it was read from a file.
+    Imported(Box<DeckImport>),
+    Back,
+}
+
+#[derive(Debug)]
+pub enum DeckEditorOutput {
+    Search(CardQuery),
+    /// Looks up the cards of a deck read from a `ydke://` URL, checking them against the
+    /// owned cards if given.
+    Resolve(Box<Deck>, Option<HashMap<Card, u8>>),
+    /// Reads a `.ydk` file, like [`DeckEditorOutput::Resolve`].
+    ImportYdk(PathBuf, Option<HashMap<Card, u8>>),
+    /// The collection with the given file name was saved.
+    Changed(String),
+    Close(String),
+}
+
+pub struct DeckEditor {
+    file_name: String,
+    collection: Collection,
+    card_infos: HashMap<CardType, CardInfo>,
+    banlist: HashMap<CardType, LimitStatus>,
+    search_results: Vec<CardInfo>,
+    selected: usize,
+    check_owned: bool,
+    file_chooser: Option<gtk::FileChooserNative>,
+}
+
+#[relm4::component(pub)]
+impl Component for DeckEditor {
+    type Init = ();
+    type Input = DeckEditorInput;
+    type Output = DeckEditorOutput;
+    type CommandOutput = ();
+    type Widgets = DeckEditorWidgets;
+
+    view! {
+        #[root]
+        gtk::Box {
+            set_orientation: Orientation::Vertical,
+
+            #[template]
+            templates::Page {
+                #[template_child]
+                back_button {
+                    connect_clicked => DeckEditorInput::Back,
+                },
+
+                #[template_child]
+                header {
+                    pack_end = &gtk::Button {
+                        set_icon_name: "user-trash-symbolic",
+                        set_tooltip_text: Some("Delete deck"),
+                        connect_clicked => DeckEditorInput::DeleteDeck,
+                    },
+                    pack_end = &gtk::Button {
+                        set_icon_name: "list-add",
+                        set_tooltip_text: Some("New deck"),
+                        connect_clicked => DeckEditorInput::NewDeck,
+                    },
+                    pack_end = &gtk::Button {
+                        set_icon_name: "edit-copy-symbolic",
+                        set_tooltip_text: Some("Copy deck as ydke:// URL"),
+                        connect_clicked => DeckEditorInput::CopyYdke,
+                    },
+                    pack_end = &gtk::Button {
+                        set_icon_name: "edit-paste-symbolic",
+                        set_tooltip_text: Some("Import ydke:// URL from clipboard"),
+                        connect_clicked => DeckEditorInput::PasteYdke,
+                    },
+                    pack_end = &gtk::Button {
+                        set_icon_name: "document-save-symbolic",
+                        set_tooltip_text: Some("Export deck as YDK"),
+                        connect_clicked => DeckEditorInput::ExportYdk,
+                    },
+                    pack_end = &gtk::Button {
+                        set_icon_name: "document-open-symbolic",
+                        set_tooltip_text: Some("Import YDK deck"),
+                        connect_clicked => DeckEditorInput::ImportYdk,
+                    },
+                    pack_end = &gtk::ToggleButton {
+                        set_icon_name: "emblem-ok-symbolic",
+                        set_tooltip_text: Some("Check imported decks against the collection"),
+                        set_active: true,
+                        connect_toggled[sender] => move |button| {
+                            sender.input(DeckEditorInput::CheckOwned(button.is_active()));
+                        },
+                    },
+                    pack_end: deck_dropdown = &gtk::DropDown {
+                        connect_selected_notify[sender] => move |dropdown| {
+                            sender.input(DeckEditorInput::SelectDeck(dropdown.selected()));
+                        },
+                    },
+                },
+
+                #[template_child]
+                window_title {
+                    set_title: "Deck editor",
+                    #[watch]
+                    set_subtitle: &model.collection.meta_data.name,
+                },
+
+                #[name = "import_banner"]
+                adw::Banner {
+                    set_button_label: Some("Dismiss"),
+                    connect_button_clicked: |banner| banner.set_revealed(false),
+                },
+
+                gtk::Box::new(Orientation::Horizontal, 12) {
+                    set_vexpand: true,
+                    set_homogeneous: true,
+                    set_margin_all: 12,
+
+                    gtk::Box::new(Orientation::Vertical, 6) {
+                        gtk::SearchEntry {
+                            set_placeholder_text: Some("Search your cards"),
+                            connect_search_changed[sender] => move |search_entry| {
+                                sender.input(DeckEditorInput::SearchChanged(search_entry.text().to_string()));
+                            },
+                        },
+                        gtk::ScrolledWindow {
+                            set_vexpand: true,
+                            set_hscrollbar_policy: gtk::PolicyType::Never,
+
+                            #[name = "search_list"]
+                            gtk::ListBox {
+                                set_valign: Align::Start,
+                                add_css_class: "boxed-list",
+                                set_selection_mode: gtk::SelectionMode::None,
+                            },
+                        },
+                    },
+
+                    gtk::ScrolledWindow {
+                        set_hscrollbar_policy: gtk::PolicyType::Never,
+
+                        #[name = "deck_box"]
+                        gtk::Box::new(Orientation::Vertical, 6) {
+                            #[name = "deck_name_row"]
+                            adw::EntryRow {
+                                set_title: "Deck name",
+                                add_css_class: "card",
+                                set_show_apply_button: true,
+                                connect_apply => DeckEditorInput::RenameDeck,
+                            },
+                        },
+                    },
+                },
+            },
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: &Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = Self {
+            file_name: String::new(),
+            collection: Collection::default(),
+            card_infos: HashMap::new(),
+            banlist: HashMap::new(),
+            search_results: Vec::new(),
+            selected: 0,
+            check_owned: true,
+            file_chooser: None,
+        };
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        input: Self::Input,
+        sender: ComponentSender<Self>,
+        root: &Self::Root,
+    ) {
+        match input {
+            DeckEditorInput::Show(file_name, collection, card_infos, banlist) => {
+                self.file_name = file_name;
+                self.collection = *collection;
+                self.card_infos = card_infos;
+                self.banlist = banlist;
+                self.selected = 0;
+
+                // Decks are only written once they are changed.
+                if self.collection.decks.is_empty() {
+                    self.collection.decks.push(Deck::new("Deck 1".to_string()));
+                }
+
+                widgets.import_banner.set_revealed(false);
+                self.refresh_deck_list(widgets);
+                sender.input(DeckEditorInput::SearchChanged(String::new()));
+            }
+            DeckEditorInput::SearchChanged(text) => {
+                let owned: Vec<CardType> =
+                    self.collection.cards.keys().map(|card| card.id).collect();
+
+                sender
+                    .output(DeckEditorOutput::Search(
+                        CardQuery::new()
+                            .text(&text)
+                            .within(owned)
+                            .page(0, SEARCH_PAGE_SIZE),
+                    ))
+                    .expect("Failed to send message `DeckEditorOutput::Search`.");
+            }
+            DeckEditorInput::SearchResults(results) => {
+                self.search_results = results;
+            }
+            DeckEditorInput::Add(section, id) => {
+                let deck = &mut self.collection.decks[self.selected];
+                if deck.add(section, id, &self.collection.cards) {
+                    self.save(&sender);
+                }
+            }
+            DeckEditorInput::Remove(section, id) => {
+                if self.collection.decks[self.selected].remove(section, id) {
+                    self.save(&sender);
+                }
+            }
+            DeckEditorInput::SelectDeck(index) => {
+                let index = index as usize;
+                if index == self.selected || index >= self.collection.decks.len() {
+                    return;
+                }
+
+                self.selected = index;
+                widgets.deck_name_row.set_text(&self.deck().name);
+            }
+            DeckEditorInput::NewDeck => {
+                let name = format!("Deck {}", self.collection.decks.len() + 1);
+                self.collection.decks.push(Deck::new(name));
+                self.selected = self.collection.decks.len() - 1;
+
+                self.save(&sender);
+                self.refresh_deck_list(widgets);
+            }
+            DeckEditorInput::RenameDeck => {
+                let name = widgets.deck_name_row.text().trim().to_string();
+                if !name.is_empty() {
+                    self.collection.decks[self.selected].name = name;
+                    self.save(&sender);
+                }
+
+                self.refresh_deck_list(widgets);
+            }
+            DeckEditorInput::DeleteDeck => {
+                self.collection.decks.remove(self.selected);
+                if self.collection.decks.is_empty() {
+                    self.collection.decks.push(Deck::new("Deck 1".to_string()));
+                }
+                self.selected = self.selected.min(self.collection.decks.len() - 1);
+
+                self.save(&sender);
+                self.refresh_deck_list(widgets);
+            }
+            DeckEditorInput::CheckOwned(check_owned) => self.check_owned = check_owned,
+            DeckEditorInput::ImportYdk => {
+                self.choose_ydk(
+                    root,
+                    &sender,
+                    "Import deck",
+                    gtk::FileChooserAction::Open,
+                    DeckEditorInput::ImportFile,
+                )
+                .show();
+            }
+            DeckEditorInput::ExportYdk => {
+                let name = format!("{}.{}", self.deck().name, YDK_EXTENSION);
+                let dialog = self.choose_ydk(
+                    root,
+                    &sender,
+                    "Export deck",
+                    gtk::FileChooserAction::Save,
+                    DeckEditorInput::ExportFile,
+                );
+                dialog.set_current_name(&name);
+                dialog.show();
+            }
+            DeckEditorInput::ImportFile(path) => sender
+                .output(DeckEditorOutput::ImportYdk(path, self.owned_to_check()))
+                .expect("Failed to send message `DeckEditorOutput::ImportYdk`."),
+            DeckEditorInput::ExportFile(path) => {
+                if let Err(error) = write_ydk(self.deck(), &path) {
+                    log::error!("Failed to export `{}`: {}", path.display(), error);
+                }
+            }
+            DeckEditorInput::CopyYdke => root.clipboard().set_text(&to_ydke(self.deck())),
+            DeckEditorInput::PasteYdke => {
+                let sender = sender.clone();
+                root.clipboard()
+                    .read_text_async(None::<&gtk::gio::Cancellable>, move |text| match text {
+                        Ok(Some(text)) => sender.input(DeckEditorInput::ImportYdke(text.into())),
+                        Ok(None) => log::error!("The clipboard does not contain text."),
+                        Err(error) => log::error!("Failed to read the clipboard: {}", error),
+                    });
+            }
+            DeckEditorInput::ImportYdke(url) => match parse_ydke(String::new(), &url) {
+                Ok(deck) => sender
+                    .output(DeckEditorOutput::Resolve(
+                        Box::new(deck),
+                        self.owned_to_check(),
+                    ))
+                    .expect("Failed to send message `DeckEditorOutput::Resolve`."),
+                Err(error) => log::error!("Failed to import deck from clipboard: {}", error),
+            },
+            DeckEditorInput::Imported(import) => {
+                let DeckImport {
+                    mut deck,
+                    infos,
+                    unknown,
+                    not_owned,
+                } = *import;
+
+                if deck.name.is_empty() {
+                    deck.name = format!("Deck {}", self.collection.decks.len() + 1);
+                }
+                self.card_infos.extend(infos);
+                self.collection.decks.push(deck);
+                self.selected = self.collection.decks.len() - 1;
+
+                let mut problems = Vec::new();
+                if !unknown.is_empty() {
+                    let unknown: Vec<String> = unknown.iter().map(|id| id.to_string()).collect();
+                    problems.push(format!("Skipped unknown card ids: {}.", unknown.join(", ")));
+                }
+                if !not_owned.is_empty() {
+                    let missing: Vec<String> = not_owned
+                        .iter()
+                        .filter_map(|violation| match violation {
+                            Violation::NotOwned {
+                                card,
+                                copies,
+                                owned,
+                            } => Some(format!(
+                                "{} ({} of {})",
+                                self.card_name(*card),
+                                copies - owned,
+                                copies
+                            )),
+                            _ => None,
+                        })
+                        .collect();
+                    problems.push(format!(
+                        "Missing from the collection: {}.",
+                        missing.join(", ")
+                    ));
+                }
+                widgets.import_banner.set_title(&problems.join(" "));
+                widgets.import_banner.set_revealed(!problems.is_empty());
+
+                self.save(&sender);
+                self.refresh_deck_list(widgets);
+            }
+            DeckEditorInput::Back => {
+                sender
+                    .output(DeckEditorOutput::Close(self.file_name.clone()))
+                    .expect("Failed to send message `DeckEditorOutput::Close`.");
+                return;
+            }
+        }
+
+        self.refresh_deck(widgets, &sender);
+        self.refresh_search_results(widgets, &sender);
+        self.update_view(widgets, sender);
+    }
+}
+
+impl DeckEditor {
+    fn deck(&self) -> &Deck {
+        &self.collection.decks[self.selected]
+    }
+
+    /// Owned cards to check imported decks against, if enabled.
+    fn owned_to_check(&self) -> Option<HashMap<Card, u8>> {
+        self.check_owned.then(|| self.collection.cards.clone())
+    }
+
+    fn save(&mut self, sender: &ComponentSender<Self>) {
+        if let Err(error) = self.collection.save(&self.file_name) {
+            log::error!("Failed to save collection `{}`: {}", self.file_name, error);
+            return;
+        }
+
+        sender
+            .output(DeckEditorOutput::Changed(self.file_name.clone()))
+            .expect("Failed to send message `DeckEditorOutput::Changed`.");
+    }
+
+    /// Shows a dialog for `.ydk` files that sends the chosen path as `message`.
+    fn choose_ydk(
+        &mut self,
+        root: &gtk::Box,
+        sender: &ComponentSender<Self>,
+        title: &str,
+        action: gtk::FileChooserAction,
+        message: fn(PathBuf) -> DeckEditorInput,
+    ) -> &gtk::FileChooserNative {
+        let sender = sender.clone();
+        let dialog = file_chooser(
+            root,
+            title,
+            action,
+            &[("YDK decks", &[YDK_PATTERN])],
+            move |path| sender.input(message(path)),
+        );
+
+        self.file_chooser.insert(dialog)
+    }
+
+    fn refresh_deck_list(&self, widgets: &mut DeckEditorWidgets) {
+        let names: Vec<&str> = self
+            .collection
+            .decks
+            .iter()
+            .map(|deck| deck.name.as_str())
+            .collect();
+
+        widgets
+            .deck_dropdown
+            .set_model(Some(&gtk::StringList::new(&names)));
+        widgets.deck_dropdown.set_selected(self.selected as u32);
+        widgets.deck_name_row.set_text(&self.deck().name);
+    }
+
+    fn violations(&self) -> Vec<Violation> {
+        validate_with_infos(
+            self.deck(),
+            &self.card_infos,
+            &self.banlist,
+            Some(&self.collection.cards),
+        )
+    }
+
+    fn card_name(&self, id: CardType) -> String {
+        match self.card_infos.get(&id) {
+            Some(info) => info.name.clone(),
+            None => id.to_string(),
+        }
+    }
+
+    fn card_row(&self, id: CardType) -> adw::ActionRow {
+        let row = adw::ActionRow::new();
+
+        row.set_title(&self.card_name(id));
+        if let Some(info) = self.card_infos.get(&id) {
+            row.set_subtitle(&info.card_type);
+        }
+
+        row
+    }
+
+    fn refresh_search_results(
+        &self,
+        widgets: &mut DeckEditorWidgets,
+        sender: &ComponentSender<Self>,
+    ) {
+        clear_list(&widgets.search_list);
+
+        for info in self.search_results.iter() {
+            let available = self
+                .deck()
+                .available_copies(info.id, &self.collection.cards);
+            let section = match info.is_extra_deck() {
+                true => DeckSection::Extra,
+                false => DeckSection::Main,
+            };
+
+            let id = info.id;
+            let row = self.card_row(id);
+            row.add_prefix(&gtk::Label::new(Some(&format!("{}×", available))));
+            row.add_suffix(&action_button(
+                gtk::Button::with_label(match section {
+                    DeckSection::Extra => "Extra",
+                    _ => "Main",
+                }),
+                available > 0,
+                sender,
+                move || DeckEditorInput::Add(section, id),
+            ));
+            row.add_suffix(&action_button(
+                gtk::Button::with_label("Side"),
+                available > 0,
+                sender,
+                move || DeckEditorInput::Add(DeckSection::Side, id),
+            ));
+
+            widgets.search_list.append(&row);
+        }
+    }
+
+    fn refresh_deck(&self, widgets: &mut DeckEditorWidgets, sender: &ComponentSender<Self>) {
+        // Everything after the name row is rebuilt.
+        while let Some(child) = widgets.deck_name_row.next_sibling() {
+            widgets.deck_box.remove(&child);
+        }
+
+        let violations = self.violations();
+
+        let problem_list = gtk::ListBox::new();
+        problem_list.add_css_class("boxed-list");
+        problem_list.set_selection_mode(gtk::SelectionMode::None);
+        for violation in violations.iter() {
+            let row = adw::ActionRow::new();
+            row.add_prefix(&gtk::Image::from_icon_name("dialog-warning-symbolic"));
+            row.set_title(&match violation.card() {
+                Some(id) => format!("{}: {}", self.card_name(id), violation),
+                None => violation.to_string(),
+            });
+            problem_list.append(&row);
+        }
+        problem_list.set_visible(!violations.is_empty());
+        widgets.deck_box.append(&problem_list);
+
+        for section in DeckSection::ALL {
+            let cards = self.deck().section(section);
+
+            let heading = gtk::Label::new(Some(&format!("{} ({})", section.title(), cards.len())));
+            heading.add_css_class("heading");
+            heading.set_halign(Align::Start);
+            heading.set_margin_top(6);
+            widgets.deck_box.append(&heading);
+
+            let list = gtk::ListBox::new();
+            list.add_css_class("boxed-list");
+            list.set_selection_mode(gtk::SelectionMode::None);
+
+            for (id, count) in self.deck().counts(section) {
+                let row = self.card_row(id);
+                row.add_prefix(&gtk::Label::new(Some(&format!("{}×", count))));
+
+                let problems: Vec<String> = violations
+                    .iter()
+                    .filter(|violation| violation.card() == Some(id))
+                    .map(|violation| violation.to_string())
+                    .collect();
+                if !problems.is_empty() {
+                    let icon = gtk::Image::from_icon_name("dialog-warning-symbolic");
+                    icon.add_css_class("warning");
+                    icon.set_tooltip_text(Some(&problems.join("\n")));
+                    row.add_suffix(&icon);
+                }
+
+                row.add_suffix(&action_button(
+                    icon_button("list-remove", "Remove a copy"),
+                    true,
+                    sender,
+                    move || DeckEditorInput::Remove(section, id),
+                ));
+                row.add_suffix(&action_button(
+                    icon_button("list-add", "Add a copy"),
+                    self.deck().available_copies(id, &self.collection.cards) > 0,
+                    sender,
+                    move || DeckEditorInput::Add(section, id),
+                ));
+                list.append(&row);
+            }
+
+            list.set_visible(list.first_child().is_some());
+            widgets.deck_box.append(&list);
+        }
+    }
+}
+
+fn clear_list(list: &gtk::ListBox) {
+    while let Some(row) = list.first_child() {
+        list.remove(&row);
+    }
+}
+
+fn action_button(
+    button: gtk::Button,
+    sensitive: bool,
+    sender: &ComponentSender<DeckEditor>,
+    message: impl Fn() -> DeckEditorInput + 'static,
+) -> gtk::Button {
+    button.set_sensitive(sensitive);
+    button.set_valign(Align::Center);
+    button.add_css_class("flat");
+
+    let sender = sender.clone();
+    button.connect_clicked(move |_| sender.input(message()));
+
+    button
+}
+
+fn icon_button(icon_name: &str, tooltip: &str) -> gtk::Button {
+    let button = gtk::Button::from_icon_name(icon_name);
+    button.set_tooltip_text(Some(tooltip));
+    button
+}
@@ -6,6 +6,7 @@ use adw::prelude::*;
 use gtk::Orientation;
 use relm4::prelude::*;
 
+use crate::user_data::collection::DraftFormat;
 use draft_box::*;
 
 #[derive(Debug)]
@@ -36,6 +37,12 @@ impl DraftContainerParams {
     }
 }
 
+impl From<&DraftFormat> for DraftContainerParams {
+    fn from(format: &DraftFormat) -> Self {
+        Self::new(format.boxes_per_offer, format.picks_per_offer)
+    }
+}
+
 #[derive(Debug)]
 pub struct DraftContainer {
     number_of_boxes: usize,
@@ -84,6 +91,13 @@ impl Component for DraftContainer {
                 while let Some(draft_box) = self.boxes.pop() {
                     root.remove(draft_box.widget());
                 }
+                self.selected_boxes.clear();
+                if self.selection_valid {
+                    self.selection_valid = false;
+                    sender
+                        .output(DraftContainerOutput::SelectionValid(false))
+                        .expect("Failed to send message `DraftContainerOutput::SelectionValid`.");
+                }
 
                 if cards.len() != self.number_of_boxes {
                     panic!(
@@ -0,0 +1,280 @@
+use std::collections::VecDeque;
+use std::sync::atomic::AtomicBool;
+
+use adw::prelude::*;
+use chrono::prelude::*;
+use gtk::Orientation;
+use relm4::prelude::*;
+
+use crate::images::{ImageKind, ImageManager};
+use crate::ui::components::{
+    DraftContainer, DraftContainerInput, DraftContainerOutput, DraftContainerParams,
+};
+use crate::ui::templates;
+use crate::user_data::collection::{
+    Card, CardType, Change, ChangeContent, Collection, LAST_CHANGED_FORMAT,
+};
+
+#[derive(Debug)]
+pub enum DraftPageInput {
+    /// Starts drafting the remaining offers of the collection's current round.
+    Start(String, Box<Collection>, Vec<Vec<Vec<CardType>>>),
+    SelectionValid(bool),
+    Confirm,
+    Picked(Vec<CardType>),
+    Back,
+}
+
+#[derive(Debug)]
+pub enum DraftPageOutput {
+    /// The collection with the given file name was saved.
+    Changed(String),
+    /// Every offer of the current round was picked from.
+    RoundFinished(String),
+    Close(String),
+}
+
+#[derive(Debug)]
+pub enum DraftPageCommandOutput {
+    /// The images of the offer requested with the given number were fetched.
+    ImagesFetched(usize),
+}
+
+pub struct DraftPage {
+    file_name: String,
+    collection: Collection,
+    offers: VecDeque<Vec<Vec<CardType>>>,
+    draft_container: Option<Controller<DraftContainer>>,
+    selection_valid: bool,
+    /// Number of the latest image request. Older requests are ignored once they finish.
+    image_request: usize,
+    loading_images: bool,
+}
+
+#[relm4::component(pub)]
+impl Component for DraftPage {
+    type Init = ();
+    type Input = DraftPageInput;
+    type Output = DraftPageOutput;
+    type CommandOutput = DraftPageCommandOutput;
+    type Widgets = DraftPageWidgets;
+
+    view! {
+        #[root]
+        gtk::Box {
+            set_orientation: Orientation::Vertical,
+
+            #[template]
+            templates::Page {
+                #[template_child]
+                back_button {
+                    connect_clicked => DraftPageInput::Back,
+                },
+
+                #[template_child]
+                header {
+                    pack_end = &gtk::Button {
+                        set_label: "Confirm",
+                        add_css_class: "suggested-action",
+                        #[watch]
+                        set_sensitive: model.selection_valid && !model.loading_images,
+                        connect_clicked => DraftPageInput::Confirm,
+                    },
+                },
+
+                #[template_child]
+                window_title {
+                    #[watch]
+                    set_title: &format!("Round {}", model.collection.progression.current_round()),
+                    #[watch]
+                    set_subtitle: &format!(
+                        "Offer {} of {}",
+                        model.collection.progression.completed_offers + 1,
+                        model.collection.progression.completed_offers as usize + model.offers.len(),
+                    ),
+                },
+
+                #[name = "error_banner"]
+                adw::Banner {
+                    set_button_label: Some("Dismiss"),
+                    connect_button_clicked: |banner| banner.set_revealed(false),
+                },
+
+                gtk::ScrolledWindow {
+                    set_vexpand: true,
+                    set_hscrollbar_policy: gtk::PolicyType::Never,
+
+                    #[name = "container_box"]
+                    gtk::Box::new(Orientation::Vertical, 0) {
+                        set_margin_all: 12,
+                        #[watch]
+                        set_sensitive: !model.loading_images,
+                    },
+                },
+            },
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: &Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = Self {
+            file_name: String::new(),
+            collection: Collection::default(),
+            offers: VecDeque::new(),
+            draft_container: None,
+            selection_valid: false,
+            image_request: 0,
+            loading_images: false,
+        };
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        input: Self::Input,
+        sender: ComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        match input {
+            DraftPageInput::Start(file_name, collection, offers) => {
+                self.file_name = file_name;
+                self.collection = *collection;
+                self.offers = offers.into();
+                widgets.error_banner.set_revealed(false);
+
+                if let Some(draft_container) = self.draft_container.take() {
+                    widgets.container_box.remove(draft_container.widget());
+                }
+
+                let draft_container = DraftContainer::builder()
+                    .launch(DraftContainerParams::from(&self.collection.draft_format))
+                    .forward(sender.input_sender(), |message| match message {
+                        DraftContainerOutput::SelectionValid(valid) => {
+                            DraftPageInput::SelectionValid(valid)
+                        }
+                        DraftContainerOutput::SelectionComplete(cards) => {
+                            DraftPageInput::Picked(cards)
+                        }
+                    });
+                widgets.container_box.append(draft_container.widget());
+                self.draft_container = Some(draft_container);
+
+                self.show_next_offer(&sender);
+            }
+            DraftPageInput::SelectionValid(valid) => self.selection_valid = valid,
+            DraftPageInput::Confirm => {
+                if let Some(draft_container) = &self.draft_container {
+                    draft_container.emit(DraftContainerInput::RequestSelected);
+                }
+            }
+            DraftPageInput::Picked(cards) => {
+                if self.offers.is_empty() {
+                    return;
+                }
+
+                let round = self.collection.progression.current_round();
+                self.collection.add_change(Change::Add(ChangeContent::new(
+                    cards.into_iter().map(|id| Card { id }).collect(),
+                    format!("{}", Utc::now().format(LAST_CHANGED_FORMAT)),
+                    Some(round),
+                )));
+                self.collection.progression.completed_offers += 1;
+                if let Err(error) = self.collection.save(&self.file_name) {
+                    log::error!("Failed to save collection `{}`: {}", self.file_name, error);
+
+                    // Keep the offer so the pick can be confirmed again.
+                    self.collection.undo_change();
+                    self.collection.progression.completed_offers -= 1;
+                    widgets
+                        .error_banner
+                        .set_title(&format!("Failed to save the pick: {}", error));
+                    widgets.error_banner.set_revealed(true);
+                    return;
+                }
+
+                widgets.error_banner.set_revealed(false);
+                self.offers.pop_front();
+                sender
+                    .output(DraftPageOutput::Changed(self.file_name.clone()))
+                    .expect("Failed to send message `DraftPageOutput::Changed`.");
+
+                self.show_next_offer(&sender);
+            }
+            DraftPageInput::Back => {
+                sender
+                    .output(DraftPageOutput::Close(self.file_name.clone()))
+                    .expect("Failed to send message `DraftPageOutput::Close`.");
+            }
+        }
+
+        self.update_view(widgets, sender);
+    }
+
+    fn update_cmd_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        message: Self::CommandOutput,
+        sender: ComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        match message {
+            DraftPageCommandOutput::ImagesFetched(request) => {
+                if request != self.image_request {
+                    return;
+                }
+
+                self.loading_images = false;
+                if let (Some(offer), Some(draft_container)) =
+                    (self.offers.front(), &self.draft_container)
+                {
+                    draft_container.emit(DraftContainerInput::Populate(offer.clone()));
+                }
+            }
+        }
+
+        self.update_view(widgets, sender);
+    }
+}
+
+impl DraftPage {
+    /// Fetches the images of the next offer in the background and shows it once they
+    /// are cached.
+    fn show_next_offer(&mut self, sender: &ComponentSender<Self>) {
+        self.selection_valid = false;
+
+        match self.offers.front() {
+            Some(offer) => {
+                self.image_request += 1;
+                self.loading_images = true;
+
+                let request = self.image_request;
+                let ids: Vec<CardType> = offer.iter().flatten().copied().collect();
+                sender.spawn_oneshot_command(move || {
+                    let report = ImageManager::from_env().fetch_missing(
+                        &ids,
+                        ImageKind::Full,
+                        &AtomicBool::new(false),
+                        |_| (),
+                    );
+                    for (id, error) in report.failed {
+                        log::warn!("Failed to fetch the image of card {}: {}", id, error);
+                    }
+
+                    DraftPageCommandOutput::ImagesFetched(request)
+                });
+            }
+            None => {
+                sender
+                    .output(DraftPageOutput::RoundFinished(self.file_name.clone()))
+                    .expect("Failed to send message `DraftPageOutput::RoundFinished`.");
+            }
+        }
+    }
+}
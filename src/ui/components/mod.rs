@@ -1,5 +1,17 @@
+mod collection_creator;
+pub use collection_creator::*;
+
 mod collection_entry;
 pub use collection_entry::*;
 
+mod collection_page;
+pub use collection_page::*;
+
+mod deck_editor;
+pub use deck_editor::*;
+
 mod draft_container;
 pub use draft_container::*;
+
+mod draft_page;
+pub use draft_page::*;
@@ -0,0 +1,39 @@
+use std::path::PathBuf;
+
+use adw::prelude::*;
+
+/// Creates a native file dialog for the window of `widget` that calls `on_file` with
+/// the chosen path. `filters` are pairs of a name and the glob patterns it matches.
+///
+/// The dialog closes once dropped, so it has to be kept until it responded.
+pub fn file_chooser(
+    widget: &impl IsA<gtk::Widget>,
+    title: &str,
+    action: gtk::FileChooserAction,
+    filters: &[(&str, &[&str])],
+    on_file: impl Fn(PathBuf) + 'static,
+) -> gtk::FileChooserNative {
+    let parent = widget
+        .root()
+        .and_then(|root| root.downcast::<gtk::Window>().ok());
+    let dialog = gtk::FileChooserNative::new(Some(title), parent.as_ref(), action, None, None);
+
+    for (name, patterns) in filters {
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some(name));
+        for pattern in patterns.iter() {
+            filter.add_pattern(pattern);
+        }
+        dialog.add_filter(&filter);
+    }
+
+    dialog.connect_response(move |dialog, response| {
+        if response == gtk::ResponseType::Accept {
+            if let Some(path) = dialog.file().and_then(|file| file.path()) {
+                on_file(path);
+            }
+        }
+    });
+
+    dialog
+}
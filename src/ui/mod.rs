@@ -2,5 +2,9 @@ mod app;
 pub use app::*;
 
 pub mod components;
+
+mod file_chooser;
+pub use file_chooser::*;
+
 pub mod templates;
 pub mod widgets;
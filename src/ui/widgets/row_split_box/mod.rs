@@ -27,4 +27,11 @@ impl RowSplitBox {
         widget.set_parent(self);
         self.imp().children.borrow_mut().push(widget);
     }
+
+    pub fn clear(&self) {
+        for child in self.imp().children.take() {
+            child.unparent();
+        }
+        self.queue_allocate();
+    }
 }
@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 
 use super::Card;
 
-#[derive(Serialize, Deserialize, Default, Debug)]
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
 pub struct ChangeContent {
     pub cards: Vec<Card>,
     pub date: String,
@@ -15,7 +15,7 @@ impl ChangeContent {
     }
 }
 
-#[derive(Serialize, Deserialize, Default, Debug)]
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
 pub enum Change {
     #[default]
     None,
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Card, CardType};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeckSection {
+    Main,
+    Extra,
+    Side,
+}
+
+impl DeckSection {
+    pub const ALL: [DeckSection; 3] = [DeckSection::Main, DeckSection::Extra, DeckSection::Side];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            DeckSection::Main => "Main deck",
+            DeckSection::Extra => "Extra deck",
+            DeckSection::Side => "Side deck",
+        }
+    }
+}
+
+/// A deck built from the cards of a collection.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Deck {
+    pub name: String,
+    pub main: Vec<CardType>,
+    pub extra: Vec<CardType>,
+    pub side: Vec<CardType>,
+}
+
+impl Deck {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            ..Default::default()
+        }
+    }
+
+    pub fn section(&self, section: DeckSection) -> &Vec<CardType> {
+        match section {
+            DeckSection::Main => &self.main,
+            DeckSection::Extra => &self.extra,
+            DeckSection::Side => &self.side,
+        }
+    }
+
+    pub fn section_mut(&mut self, section: DeckSection) -> &mut Vec<CardType> {
+        match section {
+            DeckSection::Main => &mut self.main,
+            DeckSection::Extra => &mut self.extra,
+            DeckSection::Side => &mut self.side,
+        }
+    }
+
+    /// Copies of the card across all sections.
+    pub fn copies(&self, id: CardType) -> usize {
+        DeckSection::ALL
+            .iter()
+            .map(|section| {
+                self.section(*section)
+                    .iter()
+                    .filter(|card| **card == id)
+                    .count()
+            })
+            .sum()
+    }
+
+    /// Copies of the card in `owned` that are not in the deck yet.
+    pub fn available_copies(&self, id: CardType, owned: &HashMap<Card, u8>) -> usize {
+        let owned = owned.get(&Card { id }).copied().unwrap_or(0) as usize;
+
+        owned.saturating_sub(self.copies(id))
+    }
+
+    /// Adds a copy of the card to `section` if `owned` has one left. Returns whether the
+    /// card was added.
+    pub fn add(&mut self, section: DeckSection, id: CardType, owned: &HashMap<Card, u8>) -> bool {
+        if self.available_copies(id, owned) == 0 {
+            return false;
+        }
+
+        self.section_mut(section).push(id);
+        true
+    }
+
+    /// Removes a copy of the card from `section`. Returns whether there was one.
+    pub fn remove(&mut self, section: DeckSection, id: CardType) -> bool {
+        let cards = self.section_mut(section);
+
+        match cards.iter().rposition(|card| *card == id) {
+            Some(position) => {
+                cards.remove(position);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cards of `section` with their number of copies, in the order they were added.
+    pub fn counts(&self, section: DeckSection) -> Vec<(CardType, usize)> {
+        let mut counts: Vec<(CardType, usize)> = Vec::new();
+
+        for id in self.section(section) {
+            match counts.iter_mut().find(|(card, _)| card == id) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((*id, 1)),
+            }
+        }
+
+        counts
+    }
+}
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+/// Where the cards of draft offers come from. Only cards from sets that are available
+/// in the current round are ever offered.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub enum PoolSource {
+    /// Every box is a pack of one of the sets new in the current round.
+    #[default]
+    Sets,
+    /// Boxes of single cards belonging to one of the archetypes.
+    Archetypes(Vec<String>),
+    /// Boxes of single cards out of every card released so far.
+    CardPool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    #[default]
+    Allow,
+    /// A card appears at most once per offer.
+    UniqueInOffer,
+    /// Cards that were already in the collection when the round started are not offered.
+    ExcludeOwned,
+}
+
+/// Describes the draft offers of each round, e.g. "pick 1 of 3 packs" or
+/// "pick 2 of 5 singles".
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DraftFormat {
+    pub boxes_per_offer: usize,
+    pub cards_per_box: usize,
+    pub picks_per_offer: usize,
+    pub offers_per_round: u16,
+    pub pool: PoolSource,
+    pub duplicates: DuplicatePolicy,
+}
+
+impl Default for DraftFormat {
+    fn default() -> Self {
+        Self {
+            boxes_per_offer: 3,
+            cards_per_box: 10,
+            picks_per_offer: 1,
+            offers_per_round: 3,
+            pool: PoolSource::Sets,
+            duplicates: DuplicatePolicy::Allow,
+        }
+    }
+}
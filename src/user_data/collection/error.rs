@@ -0,0 +1,88 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use bincode::error::{DecodeError, EncodeError};
+
+#[derive(Debug)]
+pub enum CollectionError {
+    /// There is no collection file with the given name.
+    NotFound(String),
+    Io(io::Error),
+    Decode(DecodeError),
+    Encode(EncodeError),
+    Json(serde_json::Error),
+    Csv(csv::Error),
+    Sqlite(rusqlite::Error),
+    /// The file was written in a format this version of the app cannot read.
+    VersionMismatch {
+        found: u32,
+        supported: u32,
+    },
+}
+
+impl CollectionError {
+    /// Converts errors about missing files into [`CollectionError::NotFound`].
+    pub fn from_io(name: &str, error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::NotFound => Self::NotFound(name.to_string()),
+            _ => Self::Io(error),
+        }
+    }
+}
+
+impl fmt::Display for CollectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(name) => write!(f, "Collection `{name}` does not exist."),
+            Self::Io(error) => write!(f, "Failed to access collection: {error}"),
+            Self::Decode(error) => write!(f, "Collection file is corrupted: {error}"),
+            Self::Encode(error) => write!(f, "Failed to encode collection: {error}"),
+            Self::Json(error) => write!(f, "Invalid collection JSON: {error}"),
+            Self::Csv(error) => write!(f, "Invalid card list CSV: {error}"),
+            Self::Sqlite(error) => write!(f, "Failed to look up cards: {error}"),
+            Self::VersionMismatch { found, supported } => write!(
+                f,
+                "Collection file format version {found} is newer than the supported version {supported}."
+            ),
+        }
+    }
+}
+
+impl Error for CollectionError {}
+
+impl From<io::Error> for CollectionError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<DecodeError> for CollectionError {
+    fn from(error: DecodeError) -> Self {
+        Self::Decode(error)
+    }
+}
+
+impl From<EncodeError> for CollectionError {
+    fn from(error: EncodeError) -> Self {
+        Self::Encode(error)
+    }
+}
+
+impl From<serde_json::Error> for CollectionError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Json(error)
+    }
+}
+
+impl From<csv::Error> for CollectionError {
+    fn from(error: csv::Error) -> Self {
+        Self::Csv(error)
+    }
+}
+
+impl From<rusqlite::Error> for CollectionError {
+    fn from(error: rusqlite::Error) -> Self {
+        Self::Sqlite(error)
+    }
+}
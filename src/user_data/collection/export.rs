@@ -0,0 +1,372 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::prelude::*;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    Card, CardType, Change, ChangeContent, Collection, CollectionError, Deck, DraftFormat,
+    MetaData, Progression, LAST_CHANGED_FORMAT,
+};
+use crate::db::{cards, sets};
+
+/// Version of the JSON layout, independent of [`super::file_format::FORMAT_VERSION`].
+pub const JSON_VERSION: u32 = 1;
+
+/// JSON layout of a collection. Cards are a list because JSON object keys have to be
+/// strings.
+#[derive(Serialize, Deserialize)]
+struct JsonCollection {
+    version: u32,
+    meta_data: MetaData,
+    cards: Vec<JsonCard>,
+    changes: Vec<Change>,
+    tags: BTreeMap<String, Vec<CardType>>,
+    seed: u64,
+    progression: Progression,
+    draft_format: DraftFormat,
+    decks: Vec<Deck>,
+}
+
+/// Read first, so newer layouts are reported as such instead of as invalid.
+#[derive(Deserialize)]
+struct JsonVersion {
+    version: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonCard {
+    id: CardType,
+    quantity: u8,
+}
+
+/// Row of a card list in the CSV format of YGOPRODeck's collection export. Other
+/// columns are ignored on import.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct CsvRow {
+    cardname: String,
+    cardq: u32,
+    cardrarity: String,
+    cardset: String,
+    cardcode: String,
+    cardid: CardType,
+}
+
+impl Collection {
+    pub fn to_json(&self) -> Result<String, CollectionError> {
+        let mut cards: Vec<JsonCard> = self
+            .cards
+            .iter()
+            .map(|(card, quantity)| JsonCard {
+                id: card.id,
+                quantity: *quantity,
+            })
+            .collect();
+        cards.sort_by_key(|card| card.id);
+
+        let json = JsonCollection {
+            version: JSON_VERSION,
+            meta_data: self.meta_data.clone(),
+            cards,
+            changes: self.changes.clone(),
+            tags: self.tags.clone().into_iter().collect(),
+            seed: self.seed,
+            progression: self.progression.clone(),
+            draft_format: self.draft_format.clone(),
+            decks: self.decks.clone(),
+        };
+
+        Ok(serde_json::to_string_pretty(&json)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, CollectionError> {
+        let JsonVersion { version } = serde_json::from_str(json)?;
+        if version > JSON_VERSION {
+            return Err(CollectionError::VersionMismatch {
+                found: version,
+                supported: JSON_VERSION,
+            });
+        }
+
+        let json: JsonCollection = serde_json::from_str(json)?;
+
+        Ok(Self {
+            meta_data: json.meta_data,
+            cards: json
+                .cards
+                .into_iter()
+                .map(|card| (Card { id: card.id }, card.quantity))
+                .collect(),
+            changes: json.changes,
+            tags: json.tags.into_iter().collect(),
+            seed: json.seed,
+            progression: json.progression,
+            draft_format: json.draft_format,
+            decks: json.decks,
+        })
+    }
+
+    /// Lists the cards as YGOPRODeck compatible CSV, using the first printing of each
+    /// card for its set and rarity.
+    pub fn to_csv(&self, connection: &Connection) -> Result<String, CollectionError> {
+        let ids: Vec<CardType> = self.cards.keys().map(|card| card.id).collect();
+        let infos = cards::get_many(connection, &ids)?;
+        let mut printings = sets::get_first_printings(connection, &ids)?;
+
+        let mut rows = Vec::with_capacity(ids.len());
+        for (card, quantity) in self.cards.iter() {
+            let printing = printings.remove(&card.id);
+            rows.push(CsvRow {
+                cardname: infos
+                    .get(&card.id)
+                    .map(|info| info.name.clone())
+                    .unwrap_or_default(),
+                cardq: *quantity as u32,
+                cardrarity: printing
+                    .as_ref()
+                    .and_then(|printing| printing.rarity.clone())
+                    .unwrap_or_default(),
+                cardset: printing
+                    .as_ref()
+                    .map(|printing| printing.set_name.clone())
+                    .unwrap_or_default(),
+                cardcode: printing
+                    .and_then(|printing| printing.set_code)
+                    .unwrap_or_default(),
+                cardid: card.id,
+            });
+        }
+        rows.sort_by(|first, second| {
+            (&first.cardname, first.cardid).cmp(&(&second.cardname, second.cardid))
+        });
+
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        for row in rows {
+            writer.serialize(row)?;
+        }
+        let bytes = writer
+            .into_inner()
+            .map_err(|error| CollectionError::Io(error.into_error()))?;
+
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    /// Creates a collection whose first change adds the cards of a CSV card list.
+    pub fn from_csv(meta_data: MetaData, seed: u64, csv: &str) -> Result<Self, CollectionError> {
+        Ok(Self::from_cards(meta_data, seed, cards_from_csv(csv)?))
+    }
+
+    /// Creates a collection whose first change adds `cards`.
+    pub fn from_cards(meta_data: MetaData, seed: u64, cards: HashMap<Card, u8>) -> Self {
+        let mut collection = Self::new(meta_data, seed);
+
+        let cards: Vec<Card> = cards
+            .into_iter()
+            .flat_map(|(card, quantity)| vec![card; quantity as usize])
+            .collect();
+        if !cards.is_empty() {
+            collection.add_change(Change::Add(ChangeContent::new(
+                cards,
+                format!("{}", Utc::now().format(LAST_CHANGED_FORMAT)),
+                None,
+            )));
+        }
+
+        collection
+    }
+}
+
+/// Reads the quantities of a CSV card list. Rows of the same card, e.g. different
+/// printings, are added up.
+pub fn cards_from_csv(csv: &str) -> Result<HashMap<Card, u8>, CollectionError> {
+    let mut cards: HashMap<Card, u8> = HashMap::new();
+
+    for row in csv::Reader::from_reader(csv.as_bytes()).deserialize() {
+        let row: CsvRow = row?;
+        if row.cardid == 0 || row.cardq == 0 {
+            continue;
+        }
+
+        let quantity = cards.entry(Card { id: row.cardid }).or_default();
+        *quantity = quantity.saturating_add(row.cardq.min(u8::MAX as u32) as u8);
+    }
+
+    Ok(cards)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::testing::fixture_database;
+    use crate::draft::PackLayout;
+    use crate::user_data::collection::{DuplicatePolicy, PoolSource};
+
+    const BLUE_EYES: CardType = 89631139;
+    const KURIBOH: CardType = 40640057;
+    const DECODE_TALKER: CardType = 1861629;
+
+    fn collection() -> Collection {
+        let mut collection = Collection::from_cards(
+            MetaData {
+                name: "Export, \"quoted\"".to_string(),
+                description: "Fixture".to_string(),
+                pinned: true,
+                last_changed: "20230501_120000".to_string(),
+            },
+            42,
+            HashMap::from([
+                (Card { id: BLUE_EYES }, 3),
+                (Card { id: KURIBOH }, 1),
+                (Card { id: DECODE_TALKER }, 2),
+                (Card { id: 999 }, 1),
+            ]),
+        );
+        collection.add_change(Change::Remove(ChangeContent::new(
+            vec![Card { id: BLUE_EYES }],
+            "20230502_120000".to_string(),
+            Some(1),
+        )));
+        collection
+            .tags
+            .insert("Dragons".to_string(), vec![BLUE_EYES]);
+        collection.tags.insert("Empty".to_string(), Vec::new());
+        collection.progression = Progression {
+            start_set: Some("Metal Raiders".to_string()),
+            sets_per_round: 2,
+            packs_per_round: 12,
+            pack_layout: PackLayout::sized(5),
+            completed_rounds: 1,
+            completed_offers: 2,
+        };
+        collection.draft_format = DraftFormat {
+            pool: PoolSource::Archetypes(vec!["Blue-Eyes".to_string()]),
+            duplicates: DuplicatePolicy::ExcludeOwned,
+            ..Default::default()
+        };
+        let mut deck = Deck::new("Dragons".to_string());
+        deck.main = vec![BLUE_EYES; 2];
+        collection.decks.push(deck);
+
+        collection
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let collection = collection();
+        let decoded = Collection::from_json(&collection.to_json().unwrap()).unwrap();
+
+        assert_eq!(
+            format!("{:?}", decoded.meta_data),
+            format!("{:?}", collection.meta_data)
+        );
+        assert_eq!(decoded.cards, collection.cards);
+        assert_eq!(decoded.cards[&Card { id: BLUE_EYES }], 2);
+        assert_eq!(
+            format!("{:?}", decoded.changes),
+            format!("{:?}", collection.changes)
+        );
+        assert_eq!(decoded.tags, collection.tags);
+        assert_eq!(decoded.seed, 42);
+        assert_eq!(decoded.progression, collection.progression);
+        assert_eq!(decoded.draft_format, collection.draft_format);
+        assert_eq!(decoded.decks, collection.decks);
+    }
+
+    #[test]
+    fn json_versions() {
+        let json = collection().to_json().unwrap();
+        let newer = json.replacen(
+            &format!("\"version\": {}", JSON_VERSION),
+            &format!("\"version\": {}", JSON_VERSION + 1),
+            1,
+        );
+        assert_ne!(newer, json);
+
+        assert!(matches!(
+            Collection::from_json(&newer),
+            Err(CollectionError::VersionMismatch { found, supported: JSON_VERSION })
+                if found == JSON_VERSION + 1
+        ));
+        assert!(matches!(
+            Collection::from_json("{\"version\": 1}"),
+            Err(CollectionError::Json(_))
+        ));
+        assert!(matches!(
+            Collection::from_json("[]"),
+            Err(CollectionError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn csv_matches_ygoprodeck_columns() {
+        let connection = fixture_database();
+
+        assert_eq!(
+            collection().to_csv(&connection).unwrap(),
+            "cardname,cardq,cardrarity,cardset,cardcode,cardid\n\
+            ,1,,,,999\n\
+            Blue-Eyes White Dragon,2,Ultra Rare,Legend of Blue Eyes White Dragon,LOB-EN001,89631139\n\
+            Decode Talker,2,,,,1861629\n\
+            Kuriboh,1,Common,Starter Deck: Yugi,SDY-011,40640057\n"
+        );
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let connection = fixture_database();
+        let collection = collection();
+
+        assert_eq!(
+            cards_from_csv(&collection.to_csv(&connection).unwrap()).unwrap(),
+            collection.cards
+        );
+    }
+
+    #[test]
+    fn imports_ygoprodeck_csv() {
+        // Columns in a different order, extra columns and several printings of a card.
+        let csv = "cardid,cardname,cardset,cardcode,cardrarity,cardq,cardprice\n\
+            89631139,Blue-Eyes White Dragon,Legend of Blue Eyes White Dragon,LOB-EN001,Ultra Rare,2,50.00\n\
+            89631139,Blue-Eyes White Dragon,Starter Deck: Kaiba,SDK-001,Ultra Rare,1,5.00\n\
+            40640057,\"Kuriboh, the \"\"fluffy\"\"\",Metal Raiders,MRD-EN071,Super Rare,300,3.00\n\
+            46986414,Dark Magician,,,,0,\n";
+
+        assert_eq!(
+            cards_from_csv(csv).unwrap(),
+            HashMap::from([(Card { id: BLUE_EYES }, 3), (Card { id: KURIBOH }, u8::MAX)])
+        );
+
+        let collection = Collection::from_csv(MetaData::default(), 7, csv).unwrap();
+        assert_eq!(collection.changes.len(), 1);
+        assert_eq!(collection.cards[&Card { id: BLUE_EYES }], 3);
+        assert!(
+            Collection::from_csv(MetaData::default(), 7, "cardid,cardq\n")
+                .unwrap()
+                .changes
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn malformed_csv_rows() {
+        for csv in [
+            "cardid,cardq\n89631139,three\n",
+            "cardid,cardq\n-1,1\n",
+            "cardid,cardq\n89631139,-1\n",
+            "cardid,cardq\n89631139\n",
+            "cardid,cardq\n89631139,1,extra\n",
+            "cardid,cardq\n,1\n",
+        ] {
+            assert!(
+                matches!(cards_from_csv(csv), Err(CollectionError::Csv(_))),
+                "{csv:?}"
+            );
+        }
+
+        // Rows without an id or quantity column are skipped.
+        assert!(cards_from_csv("cardname\nBlue-Eyes White Dragon\n")
+            .unwrap()
+            .is_empty());
+    }
+}
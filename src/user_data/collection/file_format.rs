@@ -0,0 +1,742 @@
+//! Layout of collection files: [`MAGIC`], the format version as a big endian `u32`, the
+//! length of the summary block as a big endian `u32`, the bincode encoded
+//! [`CollectionSummary`] and the bincode encoded collection.
+//!
+//! The summary block lets the collection list be read without decoding cards and
+//! changes. Versions:
+//!
+//! - 0: Files written before the header was introduced, whichever fields they already
+//!   had. They cannot start with [`MAGIC`] because they begin with the length of the
+//!   collection name as a big endian `u64`.
+//! - 1: Header without summary block.
+//! - 2: Added the summary block.
+//! - 3: Added decks.
+//!
+//! To change the layout, freeze the current structs in a new `vN` module, bump
+//! [`FORMAT_VERSION`] and add an upgrade from `vN` to [`decode`].
+
+use std::collections::HashMap;
+use std::io::Read;
+
+use bincode::error::DecodeError;
+use bincode::{serde::decode_from_slice, serde::encode_to_vec};
+use serde::de::DeserializeOwned;
+
+use super::{Card, Collection, CollectionError, CollectionSummary, DraftFormat, Progression};
+use crate::data::BINCODE_CONFIG;
+use crate::draft::seed_from_bytes;
+
+pub const MAGIC: &[u8; 4] = b"YGOC";
+pub const FORMAT_VERSION: u32 = 3;
+
+const HEADER_SIZE: usize = MAGIC.len() + 4;
+/// First version with a summary block.
+const SUMMARY_VERSION: u32 = 2;
+
+/// Collections as written before the header was introduced. The types are frozen copies
+/// of their layout at the time and must not change.
+mod v0 {
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    use crate::user_data::collection::{self as current, CardType};
+
+    #[derive(Deserialize, PartialEq, Eq, Hash)]
+    pub struct Card {
+        pub id: CardType,
+    }
+
+    #[derive(Deserialize)]
+    pub struct MetaData {
+        pub name: String,
+        pub description: String,
+        pub pinned: bool,
+        pub last_changed: String,
+    }
+
+    #[derive(Deserialize)]
+    pub struct ChangeContent {
+        pub cards: Vec<Card>,
+        pub date: String,
+        pub round: Option<u16>,
+    }
+
+    #[derive(Deserialize)]
+    pub enum Change {
+        None,
+        Add(ChangeContent),
+        Remove(ChangeContent),
+    }
+
+    #[derive(Deserialize)]
+    pub struct Collection {
+        pub meta_data: MetaData,
+        pub cards: HashMap<Card, u8>,
+        pub changes: Vec<Change>,
+        pub tags: HashMap<String, Vec<CardType>>,
+    }
+
+    impl From<Card> for current::Card {
+        fn from(card: Card) -> Self {
+            Self { id: card.id }
+        }
+    }
+
+    impl From<MetaData> for current::MetaData {
+        fn from(meta_data: MetaData) -> Self {
+            Self {
+                name: meta_data.name,
+                description: meta_data.description,
+                pinned: meta_data.pinned,
+                last_changed: meta_data.last_changed,
+            }
+        }
+    }
+
+    impl From<ChangeContent> for current::ChangeContent {
+        fn from(content: ChangeContent) -> Self {
+            Self {
+                cards: content.cards.into_iter().map(Into::into).collect(),
+                date: content.date,
+                round: content.round,
+            }
+        }
+    }
+
+    impl From<Change> for current::Change {
+        fn from(change: Change) -> Self {
+            match change {
+                Change::None => Self::None,
+                Change::Add(content) => Self::Add(content.into()),
+                Change::Remove(content) => Self::Remove(content.into()),
+            }
+        }
+    }
+}
+
+/// Collections of versions 1 and 2, which only differ in the summary block. Types that
+/// did not change since version 0 are reused from [`v0`].
+mod v1 {
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    use super::v0::{Card, Change, MetaData};
+    use crate::draft;
+    use crate::user_data::collection::{self as current, CardType};
+
+    #[derive(Deserialize)]
+    pub struct Slot {
+        pub count: usize,
+        pub rarities: Vec<(String, u32)>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct PackLayout {
+        pub slots: Vec<Slot>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Progression {
+        pub start_set: Option<String>,
+        pub sets_per_round: u16,
+        pub packs_per_round: u16,
+        pub pack_layout: PackLayout,
+        pub completed_rounds: u16,
+        pub completed_offers: u16,
+    }
+
+    #[derive(Deserialize)]
+    pub enum PoolSource {
+        Sets,
+        Archetypes(Vec<String>),
+        CardPool,
+    }
+
+    #[derive(Deserialize)]
+    pub enum DuplicatePolicy {
+        Allow,
+        UniqueInOffer,
+        ExcludeOwned,
+    }
+
+    #[derive(Deserialize)]
+    pub struct DraftFormat {
+        pub boxes_per_offer: usize,
+        pub cards_per_box: usize,
+        pub picks_per_offer: usize,
+        pub offers_per_round: u16,
+        pub pool: PoolSource,
+        pub duplicates: DuplicatePolicy,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Collection {
+        pub meta_data: MetaData,
+        pub cards: HashMap<Card, u8>,
+        pub changes: Vec<Change>,
+        pub tags: HashMap<String, Vec<CardType>>,
+        pub seed: u64,
+        pub progression: Progression,
+        pub draft_format: DraftFormat,
+    }
+
+    impl From<PackLayout> for draft::PackLayout {
+        fn from(layout: PackLayout) -> Self {
+            Self {
+                slots: layout
+                    .slots
+                    .into_iter()
+                    .map(|slot| draft::Slot {
+                        count: slot.count,
+                        rarities: slot.rarities,
+                    })
+                    .collect(),
+            }
+        }
+    }
+
+    impl From<Progression> for current::Progression {
+        fn from(progression: Progression) -> Self {
+            Self {
+                start_set: progression.start_set,
+                sets_per_round: progression.sets_per_round,
+                packs_per_round: progression.packs_per_round,
+                pack_layout: progression.pack_layout.into(),
+                completed_rounds: progression.completed_rounds,
+                completed_offers: progression.completed_offers,
+            }
+        }
+    }
+
+    impl From<PoolSource> for current::PoolSource {
+        fn from(pool: PoolSource) -> Self {
+            match pool {
+                PoolSource::Sets => Self::Sets,
+                PoolSource::Archetypes(archetypes) => Self::Archetypes(archetypes),
+                PoolSource::CardPool => Self::CardPool,
+            }
+        }
+    }
+
+    impl From<DuplicatePolicy> for current::DuplicatePolicy {
+        fn from(duplicates: DuplicatePolicy) -> Self {
+            match duplicates {
+                DuplicatePolicy::Allow => Self::Allow,
+                DuplicatePolicy::UniqueInOffer => Self::UniqueInOffer,
+                DuplicatePolicy::ExcludeOwned => Self::ExcludeOwned,
+            }
+        }
+    }
+
+    impl From<DraftFormat> for current::DraftFormat {
+        fn from(format: DraftFormat) -> Self {
+            Self {
+                boxes_per_offer: format.boxes_per_offer,
+                cards_per_box: format.cards_per_box,
+                picks_per_offer: format.picks_per_offer,
+                offers_per_round: format.offers_per_round,
+                pool: format.pool.into(),
+                duplicates: format.duplicates.into(),
+            }
+        }
+    }
+}
+
+/// Headerless collections written by development builds between version 0 and version
+/// 1, each appending fields to version 0. The last of them has the layout of [`v1`].
+/// Structs are encoded without delimiters, so the fields of version 0 can be nested.
+mod unversioned {
+    use serde::Deserialize;
+
+    use super::{v0, v1};
+
+    #[derive(Deserialize)]
+    pub struct Seeded {
+        pub collection: v0::Collection,
+        pub seed: u64,
+    }
+
+    /// Progression before the draft format was split off.
+    #[derive(Deserialize)]
+    pub struct ProgressionWithOffers {
+        pub start_set: Option<String>,
+        pub sets_per_round: u16,
+        pub packs_per_round: u16,
+        pub draft_offers_per_round: u16,
+        pub pack_layout: v1::PackLayout,
+        pub completed_rounds: u16,
+    }
+
+    #[derive(Deserialize)]
+    pub struct WithProgression {
+        pub seeded: Seeded,
+        pub progression: ProgressionWithOffers,
+    }
+
+    /// Progression before picked offers were tracked.
+    #[derive(Deserialize)]
+    pub struct ProgressionWithoutPicks {
+        pub start_set: Option<String>,
+        pub sets_per_round: u16,
+        pub packs_per_round: u16,
+        pub pack_layout: v1::PackLayout,
+        pub completed_rounds: u16,
+    }
+
+    #[derive(Deserialize)]
+    pub struct WithDraftFormat {
+        pub seeded: Seeded,
+        pub progression: ProgressionWithoutPicks,
+        pub draft_format: v1::DraftFormat,
+    }
+}
+
+/// Fills the fields version 0 did not have with defaults and `seed`.
+fn upgrade_v0(collection: v0::Collection, seed: u64) -> Collection {
+    Collection {
+        meta_data: collection.meta_data.into(),
+        cards: upgrade_cards(collection.cards),
+        changes: collection.changes.into_iter().map(Into::into).collect(),
+        tags: collection.tags,
+        seed,
+        ..Default::default()
+    }
+}
+
+fn upgrade_v1(collection: v1::Collection) -> Collection {
+    Collection {
+        meta_data: collection.meta_data.into(),
+        cards: upgrade_cards(collection.cards),
+        changes: collection.changes.into_iter().map(Into::into).collect(),
+        tags: collection.tags,
+        seed: collection.seed,
+        progression: collection.progression.into(),
+        draft_format: collection.draft_format.into(),
+        decks: Vec::new(),
+    }
+}
+
+fn upgrade_seeded(collection: unversioned::Seeded) -> Collection {
+    upgrade_v0(collection.collection, collection.seed)
+}
+
+fn upgrade_with_progression(collection: unversioned::WithProgression) -> Collection {
+    let progression = collection.progression;
+
+    Collection {
+        progression: Progression {
+            start_set: progression.start_set,
+            sets_per_round: progression.sets_per_round,
+            packs_per_round: progression.packs_per_round,
+            pack_layout: progression.pack_layout.into(),
+            completed_rounds: progression.completed_rounds,
+            completed_offers: 0,
+        },
+        draft_format: DraftFormat {
+            offers_per_round: progression.draft_offers_per_round,
+            ..Default::default()
+        },
+        ..upgrade_seeded(collection.seeded)
+    }
+}
+
+fn upgrade_with_draft_format(collection: unversioned::WithDraftFormat) -> Collection {
+    let progression = collection.progression;
+
+    Collection {
+        progression: Progression {
+            start_set: progression.start_set,
+            sets_per_round: progression.sets_per_round,
+            packs_per_round: progression.packs_per_round,
+            pack_layout: progression.pack_layout.into(),
+            completed_rounds: progression.completed_rounds,
+            completed_offers: 0,
+        },
+        draft_format: collection.draft_format.into(),
+        ..upgrade_seeded(collection.seeded)
+    }
+}
+
+fn upgrade_cards(cards: HashMap<v0::Card, u8>) -> HashMap<Card, u8> {
+    cards
+        .into_iter()
+        .map(|(card, quantity)| (card.into(), quantity))
+        .collect()
+}
+
+/// Splits a file into its format version and body.
+fn split_header(bytes: &[u8]) -> Result<(u32, &[u8]), CollectionError> {
+    match bytes.strip_prefix(MAGIC) {
+        Some(rest) if rest.len() >= 4 => {
+            let version = u32::from_be_bytes(rest[..4].try_into().expect("Length checked"));
+
+            if version > FORMAT_VERSION {
+                return Err(CollectionError::VersionMismatch {
+                    found: version,
+                    supported: FORMAT_VERSION,
+                });
+            }
+
+            Ok((version, &rest[4..]))
+        }
+        _ => Ok((0, bytes)),
+    }
+}
+
+fn summary_length(body: &[u8]) -> Result<usize, CollectionError> {
+    match body.get(..4) {
+        Some(length) => Ok(u32::from_be_bytes(length.try_into().expect("Length checked")) as usize),
+        None => Err(DecodeError::UnexpectedEnd {
+            additional: 4 - body.len(),
+        }
+        .into()),
+    }
+}
+
+/// Splits the body of a file with a summary block into the summary and the collection.
+fn split_summary(body: &[u8]) -> Result<(&[u8], &[u8]), CollectionError> {
+    let length = summary_length(body)?;
+    let rest = &body[4..];
+
+    if rest.len() < length {
+        return Err(DecodeError::UnexpectedEnd {
+            additional: length - rest.len(),
+        }
+        .into());
+    }
+
+    Ok(rest.split_at(length))
+}
+
+fn decode_body<T: DeserializeOwned>(body: &[u8]) -> Result<T, CollectionError> {
+    Ok(decode_from_slice(body, BINCODE_CONFIG)?.0)
+}
+
+/// Decodes `body` only if it has exactly the layout of `T`.
+fn decode_exact<T: DeserializeOwned>(body: &[u8]) -> Option<T> {
+    match decode_from_slice(body, BINCODE_CONFIG) {
+        Ok((value, length)) if length == body.len() => Some(value),
+        _ => None,
+    }
+}
+
+/// Decodes a file without header. Its layout is the one that consumes every byte,
+/// falling back to version 0.
+///
+/// Version 0 files have no seed, so it is derived from the file to stay the same until
+/// the collection is saved in the current format.
+fn decode_headerless(body: &[u8]) -> Result<Collection, CollectionError> {
+    if let Some(collection) = decode_exact(body) {
+        return Ok(upgrade_v0(collection, seed_from_bytes(body)));
+    }
+    if let Some(collection) = decode_exact(body) {
+        return Ok(upgrade_seeded(collection));
+    }
+    if let Some(collection) = decode_exact(body) {
+        return Ok(upgrade_with_progression(collection));
+    }
+    if let Some(collection) = decode_exact(body) {
+        return Ok(upgrade_with_draft_format(collection));
+    }
+    if let Some(collection) = decode_exact(body) {
+        return Ok(upgrade_v1(collection));
+    }
+
+    Ok(upgrade_v0(decode_body(body)?, seed_from_bytes(body)))
+}
+
+pub fn encode(collection: &Collection) -> Result<Vec<u8>, CollectionError> {
+    let summary = encode_to_vec(CollectionSummary::of(collection), BINCODE_CONFIG)?;
+
+    let mut bytes = Vec::with_capacity(HEADER_SIZE + 4 + summary.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&FORMAT_VERSION.to_be_bytes());
+    bytes.extend_from_slice(&(summary.len() as u32).to_be_bytes());
+    bytes.extend(summary);
+    bytes.extend(encode_to_vec(collection, BINCODE_CONFIG)?);
+
+    Ok(bytes)
+}
+
+/// Decodes a collection file of any supported version, upgrading it to the current
+/// layout.
+pub fn decode(bytes: &[u8]) -> Result<Collection, CollectionError> {
+    let (version, body) = split_header(bytes)?;
+
+    match version {
+        0 => decode_headerless(body),
+        1 => Ok(upgrade_v1(decode_body(body)?)),
+        2 => Ok(upgrade_v1(decode_body(split_summary(body)?.1)?)),
+        _ => decode_body(split_summary(body)?.1),
+    }
+}
+
+/// Decodes only the summary of a collection file. Files without a summary block are
+/// decoded completely.
+pub fn decode_summary(bytes: &[u8]) -> Result<CollectionSummary, CollectionError> {
+    let (version, body) = split_header(bytes)?;
+
+    match version {
+        0 | 1 => Ok(CollectionSummary::of(&decode(bytes)?)),
+        _ => decode_body(split_summary(body)?.0),
+    }
+}
+
+/// Like [`decode_summary`], but only reads as much of `reader` as needed.
+pub fn read_summary<R: Read>(reader: &mut R) -> Result<CollectionSummary, CollectionError> {
+    let mut bytes = Vec::new();
+    reader
+        .by_ref()
+        .take(HEADER_SIZE as u64 + 4)
+        .read_to_end(&mut bytes)?;
+
+    let (version, body) = split_header(&bytes)?;
+    if version >= SUMMARY_VERSION {
+        // The length comes from the file, so only allocate what can actually be read.
+        let length = summary_length(body)?;
+        let mut summary = Vec::new();
+        reader
+            .by_ref()
+            .take(length as u64)
+            .read_to_end(&mut summary)?;
+        if summary.len() < length {
+            return Err(DecodeError::UnexpectedEnd {
+                additional: length - summary.len(),
+            }
+            .into());
+        }
+
+        return decode_body(&summary);
+    }
+
+    reader.read_to_end(&mut bytes)?;
+    decode_summary(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::draft::{PackLayout, Slot};
+    use crate::user_data::collection::{
+        CardType, Change, ChangeContent, Deck, DraftFormat, DuplicatePolicy, MetaData, PoolSource,
+        Progression,
+    };
+
+    const BLUE_EYES: CardType = 89631139;
+
+    /// Files written by each version. They all hold the collection of [`fixture`], except
+    /// that version 0 has no seed, progression or draft format and only version 3 has
+    /// decks.
+    const FIXTURES: [&[u8]; 4] = [
+        include_bytes!("../../../tests/fixtures/collections/v0.bin"),
+        include_bytes!("../../../tests/fixtures/collections/v1.bin"),
+        include_bytes!("../../../tests/fixtures/collections/v2.bin"),
+        include_bytes!("../../../tests/fixtures/collections/v3.bin"),
+    ];
+
+    fn fixture() -> Collection {
+        let mut collection = Collection::new(
+            MetaData {
+                name: "Legacy".to_string(),
+                description: "Fixture".to_string(),
+                pinned: true,
+                last_changed: "20230501_120000".to_string(),
+            },
+            42,
+        );
+        collection.cards.insert(Card { id: BLUE_EYES }, 3);
+        collection.changes.push(Change::Add(ChangeContent::new(
+            vec![Card { id: BLUE_EYES }; 3],
+            "20230501_120000".to_string(),
+            Some(1),
+        )));
+        collection
+            .tags
+            .insert("Dragons".to_string(), vec![BLUE_EYES]);
+        collection.progression = Progression {
+            start_set: Some("Legend of Blue Eyes White Dragon".to_string()),
+            sets_per_round: 2,
+            packs_per_round: 12,
+            pack_layout: PackLayout {
+                slots: vec![Slot::new(1, &[("Common", 1)])],
+            },
+            completed_rounds: 1,
+            completed_offers: 2,
+        };
+        collection.draft_format = DraftFormat {
+            boxes_per_offer: 5,
+            cards_per_box: 1,
+            picks_per_offer: 2,
+            offers_per_round: 4,
+            pool: PoolSource::Archetypes(vec!["Blue-Eyes".to_string()]),
+            duplicates: DuplicatePolicy::ExcludeOwned,
+        };
+        let mut deck = Deck::new("Blue-Eyes".to_string());
+        deck.main = vec![BLUE_EYES; 3];
+        collection.decks.push(deck);
+
+        collection
+    }
+
+    /// Asserts that the fields version 0 already had are equal.
+    fn assert_v0_fields_eq(actual: &Collection, expected: &Collection) {
+        assert_eq!(actual.meta_data.name, expected.meta_data.name);
+        assert_eq!(actual.meta_data.description, expected.meta_data.description);
+        assert_eq!(actual.meta_data.pinned, expected.meta_data.pinned);
+        assert_eq!(
+            actual.meta_data.last_changed,
+            expected.meta_data.last_changed
+        );
+        assert_eq!(actual.cards, expected.cards);
+        assert_eq!(
+            format!("{:?}", actual.changes),
+            format!("{:?}", expected.changes)
+        );
+        assert_eq!(actual.tags, expected.tags);
+    }
+
+    fn assert_v1_fields_eq(actual: &Collection, expected: &Collection) {
+        assert_v0_fields_eq(actual, expected);
+        assert_eq!(actual.seed, expected.seed);
+        assert_eq!(actual.progression, expected.progression);
+        assert_eq!(actual.draft_format, expected.draft_format);
+    }
+
+    #[test]
+    fn round_trip() {
+        let collection = fixture();
+        let bytes = encode(&collection).unwrap();
+
+        let decoded = decode(&bytes).unwrap();
+        assert_v1_fields_eq(&decoded, &collection);
+        assert_eq!(decoded.decks, collection.decks);
+
+        let summary = decode_summary(&bytes).unwrap();
+        assert_eq!(summary.meta_data.name, "Legacy");
+        assert_eq!(summary.card_count, 3);
+        assert_eq!(summary.round, 2);
+        assert_eq!(
+            read_summary(&mut bytes.as_slice()).unwrap().card_count,
+            summary.card_count
+        );
+    }
+
+    #[test]
+    fn current_version_matches_fixture() {
+        assert_eq!(
+            encode(&fixture()).unwrap(),
+            FIXTURES[FORMAT_VERSION as usize]
+        );
+    }
+
+    #[test]
+    fn decodes_version_0() {
+        let collection = decode(FIXTURES[0]).unwrap();
+
+        assert_v0_fields_eq(&collection, &fixture());
+        assert_eq!(collection.seed, seed_from_bytes(FIXTURES[0]));
+        assert_eq!(decode(FIXTURES[0]).unwrap().seed, collection.seed);
+        assert_eq!(collection.progression, Progression::default());
+        assert_eq!(collection.draft_format, DraftFormat::default());
+        assert!(collection.decks.is_empty());
+    }
+
+    #[test]
+    fn decodes_versions_1_and_2() {
+        for bytes in &FIXTURES[1..3] {
+            let collection = decode(bytes).unwrap();
+
+            assert_v1_fields_eq(&collection, &fixture());
+            assert!(collection.decks.is_empty());
+        }
+    }
+
+    #[test]
+    fn decodes_version_3() {
+        let collection = decode(FIXTURES[3]).unwrap();
+
+        assert_v1_fields_eq(&collection, &fixture());
+        assert_eq!(collection.decks, fixture().decks);
+    }
+
+    #[test]
+    fn decodes_unversioned_layouts() {
+        let expected = fixture();
+        let picks_untracked = Progression {
+            completed_offers: 0,
+            ..expected.progression.clone()
+        };
+
+        let seeded = decode(include_bytes!(
+            "../../../tests/fixtures/collections/unversioned_seed.bin"
+        ))
+        .unwrap();
+        assert_v0_fields_eq(&seeded, &expected);
+        assert_eq!(seeded.seed, expected.seed);
+        assert_eq!(seeded.progression, Progression::default());
+        assert_eq!(seeded.draft_format, DraftFormat::default());
+
+        let with_progression = decode(include_bytes!(
+            "../../../tests/fixtures/collections/unversioned_progression.bin"
+        ))
+        .unwrap();
+        assert_v0_fields_eq(&with_progression, &expected);
+        assert_eq!(with_progression.seed, expected.seed);
+        assert_eq!(with_progression.progression, picks_untracked);
+        assert_eq!(
+            with_progression.draft_format,
+            DraftFormat {
+                offers_per_round: expected.draft_format.offers_per_round,
+                ..Default::default()
+            }
+        );
+
+        let with_draft_format = decode(include_bytes!(
+            "../../../tests/fixtures/collections/unversioned_draft_format.bin"
+        ))
+        .unwrap();
+        assert_v0_fields_eq(&with_draft_format, &expected);
+        assert_eq!(with_draft_format.seed, expected.seed);
+        assert_eq!(with_draft_format.progression, picks_untracked);
+        assert_eq!(with_draft_format.draft_format, expected.draft_format);
+
+        let with_offers = decode(include_bytes!(
+            "../../../tests/fixtures/collections/unversioned_offers.bin"
+        ))
+        .unwrap();
+        assert_v1_fields_eq(&with_offers, &expected);
+        assert!(with_offers.decks.is_empty());
+    }
+
+    #[test]
+    fn reads_summaries_of_every_version() {
+        for (version, bytes) in FIXTURES.iter().enumerate() {
+            let summary = read_summary(&mut &bytes[..]).unwrap();
+
+            assert_eq!(summary.meta_data.name, "Legacy", "version {}", version);
+            assert_eq!(summary.card_count, 3, "version {}", version);
+            assert_eq!(summary.round, if version == 0 { 1 } else { 2 });
+        }
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let mut bytes = FIXTURES[3].to_vec();
+        bytes[MAGIC.len()..HEADER_SIZE].copy_from_slice(&(FORMAT_VERSION + 1).to_be_bytes());
+
+        assert!(matches!(
+            decode(&bytes),
+            Err(CollectionError::VersionMismatch { found, .. }) if found == FORMAT_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn rejects_truncated_summaries() {
+        let bytes = &FIXTURES[3][..HEADER_SIZE + 6];
+
+        assert!(read_summary(&mut &bytes[..]).is_err());
+        assert!(decode(bytes).is_err());
+    }
+}
@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+use std::fs;
+use std::time::SystemTime;
+
+use bincode::{serde::decode_from_slice, serde::encode_to_vec};
+use serde::{Deserialize, Serialize};
+
+use super::{Collection, CollectionError, CollectionSummary};
+use crate::data::{dirs::COLLECTIONS, files::COLLECTION_INDEX, BINCODE_CONFIG};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct IndexEntry {
+    /// Modification time of the file when the summary was read.
+    modified: SystemTime,
+    summary: CollectionSummary,
+}
+
+/// Cache of the summaries of all collection files, so listing collections does not
+/// need to open every file. Entries are dropped when a collection is saved and
+/// re-read when the file's modification time changes.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct CollectionIndex {
+    entries: HashMap<String, IndexEntry>,
+}
+
+fn modified(name: &str) -> Result<SystemTime, CollectionError> {
+    fs::metadata(COLLECTIONS.join(name))
+        .and_then(|metadata| metadata.modified())
+        .map_err(|error| CollectionError::from_io(name, error))
+}
+
+impl CollectionIndex {
+    /// Loads the cached index. A missing or unreadable index is treated as empty.
+    pub fn load() -> Self {
+        fs::read(COLLECTION_INDEX.as_path())
+            .ok()
+            .and_then(|bytes| decode_from_slice(&bytes, BINCODE_CONFIG).ok())
+            .map(|(index, _)| index)
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), CollectionError> {
+        fs::write(
+            COLLECTION_INDEX.as_path(),
+            encode_to_vec(self, BINCODE_CONFIG)?,
+        )?;
+
+        Ok(())
+    }
+
+    /// Returns the summary of the collection called `name`, reading the file if the
+    /// cached summary is missing or outdated.
+    pub fn summary(&mut self, name: &str) -> Result<CollectionSummary, CollectionError> {
+        let modified = modified(name)?;
+
+        if let Some(entry) = self.entries.get(name) {
+            if entry.modified == modified {
+                return Ok(entry.summary.clone());
+            }
+        }
+
+        let summary = Collection::read_summary(name)?;
+        self.entries.insert(
+            name.to_string(),
+            IndexEntry {
+                modified,
+                summary: summary.clone(),
+            },
+        );
+
+        Ok(summary)
+    }
+
+    /// Returns the summaries of all collection files and forgets removed ones.
+    pub fn summaries(&mut self) -> Vec<(String, Result<CollectionSummary, CollectionError>)> {
+        let names = Collection::get_names();
+        self.entries.retain(|name, _| names.contains(name));
+
+        names
+            .into_iter()
+            .map(|name| {
+                let summary = self.summary(&name);
+                (name, summary)
+            })
+            .collect()
+    }
+
+    /// Removes the cached summary of the collection called `name` from the index file.
+    pub fn invalidate(name: &str) -> Result<(), CollectionError> {
+        if !COLLECTION_INDEX.is_file() {
+            return Ok(());
+        }
+
+        let mut index = Self::load();
+        if index.entries.remove(name).is_some() {
+            index.save()?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use super::*;
+    use crate::data::dirs::COLLECTION_BACKUPS;
+    use crate::data::test_dirs;
+    use crate::user_data::collection::MetaData;
+
+    /// Removes every collection and the index.
+    fn clear() {
+        let _ = fs::remove_dir_all(COLLECTIONS.as_path());
+        let _ = fs::remove_dir_all(COLLECTION_BACKUPS.as_path());
+        let _ = fs::remove_file(COLLECTION_INDEX.as_path());
+        fs::create_dir_all(COLLECTIONS.as_path()).unwrap();
+    }
+
+    fn save(file_name: &str, name: &str) {
+        let mut collection = Collection::new(
+            MetaData {
+                name: name.to_string(),
+                ..Default::default()
+            },
+            7,
+        );
+        collection.save(file_name).unwrap();
+    }
+
+    fn names(index: &mut CollectionIndex) -> Vec<(String, String)> {
+        let mut names: Vec<(String, String)> = index
+            .summaries()
+            .into_iter()
+            .map(|(file_name, summary)| (file_name, summary.unwrap().meta_data.name))
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn save_invalidates_entry() {
+        let _dirs = test_dirs();
+        clear();
+        save("first", "First");
+        save("second", "Second");
+
+        let mut index = CollectionIndex::load();
+        names(&mut index);
+        index.save().unwrap();
+        assert_eq!(CollectionIndex::load().entries.len(), 2);
+
+        save("first", "Renamed");
+        let mut index = CollectionIndex::load();
+        assert!(!index.entries.contains_key("first"));
+        assert!(index.entries.contains_key("second"));
+        assert_eq!(index.summary("first").unwrap().meta_data.name, "Renamed");
+    }
+
+    #[test]
+    fn refreshes_outdated_entries() {
+        let _dirs = test_dirs();
+        clear();
+        save("first", "First");
+
+        let mut index = CollectionIndex::default();
+        index.summary("first").unwrap();
+        let entry = index.entries.get_mut("first").unwrap();
+        entry.summary.meta_data.name = "Cached".to_string();
+        assert_eq!(index.summary("first").unwrap().meta_data.name, "Cached");
+
+        index.entries.get_mut("first").unwrap().modified = UNIX_EPOCH;
+        assert_eq!(index.summary("first").unwrap().meta_data.name, "First");
+        assert_ne!(index.entries["first"].modified, UNIX_EPOCH);
+    }
+
+    #[test]
+    fn prunes_removed_files() {
+        let _dirs = test_dirs();
+        clear();
+        save("first", "First");
+        save("second", "Second");
+
+        let mut index = CollectionIndex::default();
+        assert_eq!(
+            names(&mut index),
+            [
+                ("first".to_string(), "First".to_string()),
+                ("second".to_string(), "Second".to_string())
+            ]
+        );
+
+        Collection::delete("second").unwrap();
+        fs::write(COLLECTIONS.join("third.part"), []).unwrap();
+        assert_eq!(
+            names(&mut index),
+            [("first".to_string(), "First".to_string())]
+        );
+        assert!(!index.entries.contains_key("second"));
+    }
+
+    #[test]
+    fn save_leaves_no_partial_files() {
+        let _dirs = test_dirs();
+        clear();
+        save("first", "First");
+        save("first", "Second");
+
+        let files = |dir: &std::path::Path| -> Vec<String> {
+            let mut files: Vec<String> = fs::read_dir(dir)
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+                .collect();
+            files.sort();
+            files
+        };
+        assert_eq!(files(&COLLECTIONS), ["first"]);
+        assert_eq!(files(&COLLECTION_BACKUPS), ["first"]);
+        assert_eq!(
+            Collection::get_metadata_from("first").unwrap().name,
+            "Second"
+        );
+    }
+}
@@ -1,9 +1,33 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Default, Debug)]
+use super::Collection;
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
 pub struct MetaData {
     pub name: String,
     pub description: String,
     pub pinned: bool,
     pub last_changed: String,
 }
+
+/// What the collection list shows, readable without decoding the whole collection.
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct CollectionSummary {
+    pub meta_data: MetaData,
+    pub card_count: u32,
+    pub round: u16,
+}
+
+impl CollectionSummary {
+    pub fn of(collection: &Collection) -> Self {
+        Self {
+            meta_data: collection.meta_data.clone(),
+            card_count: collection
+                .cards
+                .values()
+                .map(|quantity| *quantity as u32)
+                .sum(),
+            round: collection.progression.current_round(),
+        }
+    }
+}
@@ -4,69 +4,185 @@ pub use card::*;
 mod change;
 pub use change::*;
 
+mod deck;
+pub use deck::*;
+
+mod draft_format;
+pub use draft_format::*;
+
+mod error;
+pub use error::*;
+
+mod export;
+pub use export::*;
+
+pub mod file_format;
+
+mod index;
+pub use index::*;
+
 mod meta_data;
 pub use meta_data::*;
 
+mod progression;
+pub use progression::*;
+
+mod starting_pool;
+pub use starting_pool::*;
+
 use std::collections::HashMap;
+use std::ffi::OsStr;
 use std::fs;
+use std::path::PathBuf;
 
-use bincode::{serde::decode_from_slice, serde::encode_to_vec};
 use chrono::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::data::{dirs::COLLECTIONS, BINCODE_CONFIG};
+use crate::data::dirs::{COLLECTIONS, COLLECTION_BACKUPS};
 
 pub static LAST_CHANGED_FORMAT: &str = "%Y%m%d_%H%M%S";
 
+/// Extension of collection files that are still being written.
+const PARTIAL_EXTENSION: &str = "part";
+
 #[derive(Serialize, Deserialize, Default, Debug)]
 pub struct Collection {
     pub meta_data: MetaData,
     pub cards: HashMap<Card, u8>,
     pub changes: Vec<Change>,
     pub tags: HashMap<String, Vec<CardType>>,
+    /// Seed for all random operations of this collection, see [`crate::draft::round_rng`].
+    pub seed: u64,
+    pub progression: Progression,
+    pub draft_format: DraftFormat,
+    pub decks: Vec<Deck>,
 }
 
 impl Collection {
+    pub fn new(meta_data: MetaData, seed: u64) -> Self {
+        Self {
+            meta_data,
+            seed,
+            ..Default::default()
+        }
+    }
+
     pub fn get_names() -> Vec<String> {
         if let Ok(read_dir) = COLLECTIONS.read_dir() {
             read_dir
-                .map(|path| {
-                    path.expect(&format!("Failed to read path."))
-                        .file_name()
-                        .into_string()
-                        .expect("Failed to get file name.")
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| {
+                    let path = entry.path();
+                    path.is_file() && path.extension() != Some(OsStr::new(PARTIAL_EXTENSION))
                 })
+                .filter_map(|entry| entry.file_name().into_string().ok())
                 .collect()
         } else {
             Vec::new()
         }
     }
 
-    pub fn from_name(name: &String) -> Self {
-        decode_from_slice(
-            &fs::read(COLLECTIONS.join(name)).expect("Failed to read collection."),
-            BINCODE_CONFIG,
-        )
-        .expect("Failed to decode collection.")
-        .0
+    /// Returns an unused file name in [`COLLECTIONS`] derived from the collection name.
+    pub fn new_file_name(name: &str) -> String {
+        let base: String = name
+            .trim()
+            .chars()
+            .map(|c| match c.is_alphanumeric() || c == '-' {
+                true => c.to_ascii_lowercase(),
+                false => '_',
+            })
+            .collect();
+        let base = match base.is_empty() {
+            true => "collection".to_string(),
+            false => base,
+        };
+
+        let mut file_name = base.clone();
+        let mut suffix = 1;
+        while COLLECTIONS.join(&file_name).exists() {
+            suffix += 1;
+            file_name = format!("{}_{}", base, suffix);
+        }
+
+        file_name
     }
 
-    pub fn save(&mut self, name: &String) {
+    fn read(name: &str) -> Result<Vec<u8>, CollectionError> {
+        fs::read(COLLECTIONS.join(name)).map_err(|error| CollectionError::from_io(name, error))
+    }
+
+    pub fn from_name(name: &str) -> Result<Self, CollectionError> {
+        file_format::decode(&Self::read(name)?)
+    }
+
+    /// Saves the collection, keeping the previous version of the file as a backup if it
+    /// could still be read.
+    pub fn save(&mut self, name: &str) -> Result<(), CollectionError> {
         self.meta_data.last_changed = format!("{}", Utc::now().format(LAST_CHANGED_FORMAT));
-        fs::write(
-            COLLECTIONS.join(name),
-            encode_to_vec(self, BINCODE_CONFIG).unwrap(),
-        )
-        .expect("Failed to save collection.");
+        let bytes = file_format::encode(self)?;
+
+        // Only readable files are backed up. Their summary tells without decoding the
+        // whole file.
+        let path = COLLECTIONS.join(name);
+        if Self::read_summary(name).is_ok() {
+            fs::create_dir_all(COLLECTION_BACKUPS.as_path())?;
+            fs::copy(&path, Self::backup_path(name))?;
+        }
+
+        // Write to a temporary file next to the collection first so an interrupted write
+        // never leaves a truncated collection behind and the rename stays on one
+        // filesystem.
+        let partial_path = COLLECTIONS.join(format!("{}.{}", name, PARTIAL_EXTENSION));
+        fs::create_dir_all(COLLECTIONS.as_path())?;
+        fs::write(&partial_path, bytes)?;
+        fs::rename(&partial_path, &path)?;
+
+        CollectionIndex::invalidate(name)
+    }
+
+    pub fn get_metadata_from(name: &str) -> Result<MetaData, CollectionError> {
+        Ok(Self::read_summary(name)?.meta_data)
+    }
+
+    /// Reads the summary of the collection called `name` without decoding the whole
+    /// file. Prefer [`CollectionIndex::summary`] when listing collections.
+    pub fn read_summary(name: &str) -> Result<CollectionSummary, CollectionError> {
+        let mut file = fs::File::open(COLLECTIONS.join(name))
+            .map_err(|error| CollectionError::from_io(name, error))?;
+
+        file_format::read_summary(&mut file)
+    }
+
+    fn backup_path(name: &str) -> PathBuf {
+        COLLECTION_BACKUPS.join(name)
     }
 
-    pub fn get_metadata_from(name: &String) -> MetaData {
-        decode_from_slice(
-            &fs::read(COLLECTIONS.join(name)).expect("Failed to read collection."),
-            BINCODE_CONFIG,
-        )
-        .expect("Failed to decode collection.")
-        .0
+    pub fn has_backup(name: &str) -> bool {
+        Self::backup_path(name).is_file()
+    }
+
+    /// Replaces the collection file with its last readable version.
+    pub fn restore_backup(name: &str) -> Result<(), CollectionError> {
+        let backup_path = Self::backup_path(name);
+        let bytes =
+            fs::read(&backup_path).map_err(|error| CollectionError::from_io(name, error))?;
+        file_format::decode(&bytes)?;
+
+        fs::copy(backup_path, COLLECTIONS.join(name))?;
+
+        CollectionIndex::invalidate(name)
+    }
+
+    /// Removes the collection file and its backup.
+    pub fn delete(name: &str) -> Result<(), CollectionError> {
+        fs::remove_file(COLLECTIONS.join(name))
+            .map_err(|error| CollectionError::from_io(name, error))?;
+
+        if Self::has_backup(name) {
+            fs::remove_file(Self::backup_path(name))?;
+        }
+
+        CollectionIndex::invalidate(name)
     }
 
     pub fn add_change(&mut self, change: Change) {
@@ -89,7 +205,7 @@ impl Collection {
         }
     }
 
-    fn add_cards(&mut self, cards: &Vec<Card>) {
+    fn add_cards(&mut self, cards: &[Card]) {
         for card in cards.iter() {
             if let Some(quantity) = self.cards.get(card) {
                 self.cards.insert(card.clone(), quantity + 1);
@@ -99,7 +215,7 @@ impl Collection {
         }
     }
 
-    fn remove_cards(&mut self, cards: &Vec<Card>) {
+    fn remove_cards(&mut self, cards: &[Card]) {
         for card in cards.iter() {
             if let Some(quantity) = self.cards.get(card) {
                 if quantity < &1 {
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+use crate::draft::PackLayout;
+
+/// Configuration and state of a collection's progression series, in which sets become
+/// available round by round in release order.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Progression {
+    /// Name of the first set of the series. `None` starts with the oldest set.
+    pub start_set: Option<String>,
+    /// Number of sets that become available each round.
+    pub sets_per_round: u16,
+    /// Number of packs opened when a round is completed.
+    pub packs_per_round: u16,
+    pub pack_layout: PackLayout,
+    pub completed_rounds: u16,
+    /// Draft offers already picked from in the current round.
+    pub completed_offers: u16,
+}
+
+impl Progression {
+    pub fn current_round(&self) -> u16 {
+        self.completed_rounds + 1
+    }
+}
+
+impl Default for Progression {
+    fn default() -> Self {
+        Self {
+            start_set: None,
+            sets_per_round: 1,
+            packs_per_round: 24,
+            pack_layout: PackLayout::standard(),
+            completed_rounds: 0,
+            completed_offers: 0,
+        }
+    }
+}
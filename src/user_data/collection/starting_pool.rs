@@ -0,0 +1,86 @@
+use std::error::Error;
+
+use chrono::prelude::*;
+use rusqlite::Connection;
+
+use super::{Card, Change, ChangeContent, Collection, LAST_CHANGED_FORMAT};
+use crate::db::sets;
+use crate::draft::open_round_packs;
+use crate::progression::ProgressionEngine;
+
+/// Cards a new collection starts with.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum StartingPool {
+    #[default]
+    Empty,
+    /// One copy of every card in the given set, e.g. a starter deck.
+    StarterDeck(String),
+    /// Packs of the sets available in the first round of the collection's progression.
+    Packs(u16),
+}
+
+impl StartingPool {
+    pub fn cards(
+        &self,
+        connection: &Connection,
+        collection: &Collection,
+    ) -> Result<Vec<Card>, Box<dyn Error>> {
+        match self {
+            StartingPool::Empty => Ok(Vec::new()),
+            StartingPool::StarterDeck(set_name) => {
+                let checklist = sets::get_checklist(connection, set_name)?;
+
+                if checklist.is_empty() {
+                    return Err(format!("Set `{}` has no cards.", set_name).into());
+                }
+
+                Ok(checklist
+                    .into_iter()
+                    .map(|printing| Card {
+                        id: printing.card_id,
+                    })
+                    .collect())
+            }
+            StartingPool::Packs(count) => {
+                let engine = ProgressionEngine::new(connection, collection)?;
+                let set_names: Vec<String> = engine
+                    .new_sets(1)
+                    .iter()
+                    .map(|set| set.name.clone())
+                    .collect();
+
+                // Round 0 is the creation of the collection, before its first round.
+                Ok(open_round_packs(
+                    connection,
+                    collection,
+                    0,
+                    &set_names,
+                    &collection.progression.pack_layout,
+                    *count as usize,
+                )?
+                .into_iter()
+                .flatten()
+                .collect())
+            }
+        }
+    }
+
+    /// Adds the starting cards to `collection` as its first change.
+    pub fn apply(
+        &self,
+        connection: &Connection,
+        collection: &mut Collection,
+    ) -> Result<(), Box<dyn Error>> {
+        let cards = self.cards(connection, collection)?;
+
+        if !cards.is_empty() {
+            collection.add_change(Change::Add(ChangeContent::new(
+                cards,
+                format!("{}", Utc::now().format(LAST_CHANGED_FORMAT)),
+                None,
+            )));
+        }
+
+        Ok(())
+    }
+}